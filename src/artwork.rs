@@ -0,0 +1,133 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{read_info_json, term};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Thumbnail {
+    pub url: String,
+    pub id: Option<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub preference: Option<i64>,
+}
+
+impl Thumbnail {
+    fn area(&self) -> u64 {
+        self.width.unwrap_or(0) * self.height.unwrap_or(0)
+    }
+
+    /// Extension taken from the URL path, defaulting to `jpg`.
+    pub fn extension(&self) -> &str {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        let name = path.rsplit('/').next().unwrap_or_default();
+
+        match name.rsplit_once('.') {
+            Some((_, ext)) if !ext.is_empty() && ext.len() <= 4 => ext,
+            _ => "jpg",
+        }
+    }
+}
+
+/// Picks the highest resolution thumbnail, falling back to yt-dlp's preference when sizes are not
+/// known. Among equally large images jpg is preferred as it is understood by every client.
+pub fn largest(thumbnails: &[Thumbnail]) -> Option<&Thumbnail> {
+    thumbnails.iter().max_by_key(|t| {
+        (
+            t.area(),
+            t.preference.unwrap_or(i64::MIN),
+            t.extension() == "jpg",
+        )
+    })
+}
+
+/// The fields of a channel's own info.json (written by yt-dlp next to its videos) that hold the
+/// channel avatar.
+#[derive(Deserialize)]
+struct ChannelJson {
+    #[serde(rename = "_type")]
+    kind: Option<String>,
+    #[serde(default)]
+    thumbnails: Vec<Thumbnail>,
+}
+
+/// URL of the channel avatar, read from a playlist info.json in one of `dirs` (other than the
+/// videos in `known`). yt-dlp gives the avatars ids like `avatar_uncropped`.
+pub fn channel_avatar<'p>(
+    dirs: impl IntoIterator<Item = &'p Path>,
+    known: &HashSet<&Path>,
+) -> Option<String> {
+    let candidates = dirs
+        .into_iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            (name.ends_with(".info.json") || name.ends_with(".info.json.gz"))
+                && !known.contains(path.as_path())
+        });
+
+    for path in candidates {
+        let Ok(data) = read_info_json(&path) else {
+            continue;
+        };
+        let Ok(channel) = serde_json::from_slice::<ChannelJson>(&data) else {
+            continue;
+        };
+        if channel.kind.as_deref() != Some("playlist") {
+            continue;
+        }
+        let avatars = channel
+            .thumbnails
+            .into_iter()
+            .filter(|t| t.id.as_deref().is_some_and(|id| id.starts_with("avatar")))
+            .collect::<Vec<_>>();
+        if let Some(avatar) = largest(&avatars) {
+            return Some(avatar.url.clone());
+        }
+    }
+
+    None
+}
+
+/// Per-user cache directory of the organizer, `name` being one of its subdirectories.
+pub fn cache_dir(name: &str) -> PathBuf {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+
+    base.join("ytdlp-organise").join(name)
+}
+
+/// Returns the path of a locally cached copy of the thumbnail, downloading it first if needed.
+pub fn fetch(video_id: &str, thumbnail: &Thumbnail) -> anyhow::Result<PathBuf> {
+    let dir = cache_dir("artwork");
+    let name = match &thumbnail.id {
+        Some(thumb_id) => format!("{video_id}-{thumb_id}.{}", thumbnail.extension()),
+        None => format!("{video_id}.{}", thumbnail.extension()),
+    };
+    let path = dir.join(name.replace('/', "_"));
+
+    if path.exists() {
+        return Ok(path);
+    }
+
+    term::print(format_args!("Downloading: {}", thumbnail.url));
+    let data = ureq::get(&thumbnail.url)
+        .call()?
+        .into_body()
+        .read_to_vec()?;
+
+    std::fs::create_dir_all(&dir)?;
+    let tmp = path.with_extension("part");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, &path)?;
+
+    Ok(path)
+}
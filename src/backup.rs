@@ -0,0 +1,126 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use chrono::Local;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+
+use crate::{confirm, manifest::Manifest, state::State, term, write_json};
+
+/// How many rotated copies of the state and manifest are kept next to them, `<file>.1` the newest.
+const KEEP: usize = 7;
+
+/// A new copy is made when the newest one is older than this, so a day of runs costs one backup.
+const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Subcommand, Clone)]
+pub enum StateAction {
+    /// Write the numbering state and the manifest of the target into one file
+    Export {
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Replace the numbering state and the manifest of the target with an exported file, the
+    /// current ones are backed up first
+    Import { input: PathBuf },
+}
+
+/// Everything the target keeps between runs, as written by `state export`.
+#[derive(Serialize, Deserialize)]
+struct Export {
+    exported: String,
+    state: State,
+    manifest: Manifest,
+}
+
+pub fn run(action: &StateAction, target: &Path, verbose: bool) -> anyhow::Result<()> {
+    match action {
+        StateAction::Export { output } => {
+            let export = Export {
+                exported: Local::now().to_rfc3339(),
+                state: State::load(target)?,
+                manifest: Manifest::load(target)?,
+            };
+            write_json(output, &export)?;
+            if verbose {
+                term::print(format_args!(
+                    "Exported {} channels and {} links to {output:?}",
+                    export.state.channels.len(),
+                    export.manifest.links.len()
+                ));
+            }
+        }
+        StateAction::Import { input } => {
+            let file =
+                File::open(input).with_context(|| format!("Can't open the export {input:?}"))?;
+            let export: Export = serde_json::from_reader(BufReader::new(file))
+                .with_context(|| format!("{input:?} isn't a state export"))?;
+            if !confirm::confirm(&format!(
+                "Replace the numbering state and manifest of {target:?} with {input:?}?"
+            ))? {
+                return Ok(());
+            }
+
+            for path in [State::path(target), Manifest::path(target)] {
+                rotate(&path)?;
+            }
+            export.state.save(target)?;
+            export.manifest.save(target)?;
+            if verbose {
+                term::print(format_args!(
+                    "Imported {} channels and {} links exported {}",
+                    export.state.channels.len(),
+                    export.manifest.links.len(),
+                    export.exported
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies `path` to `<path>.1` unless that copy is recent, shifting older copies up and dropping
+/// the oldest.
+pub fn rotate_daily(path: &Path) -> anyhow::Result<()> {
+    let recent = backup(path, 1)
+        .metadata()
+        .and_then(|meta| meta.modified())
+        .is_ok_and(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age < INTERVAL)
+        });
+    if recent {
+        return Ok(());
+    }
+
+    rotate(path)
+}
+
+fn rotate(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    for n in (1..KEEP).rev() {
+        match std::fs::rename(backup(path, n), backup(path, n + 1)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    std::fs::copy(path, backup(path, 1)).with_context(|| format!("Can't back up {path:?}"))?;
+
+    Ok(())
+}
+
+fn backup(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
@@ -0,0 +1,43 @@
+use std::{fs::File, path::Path};
+
+use anyhow::Context;
+
+use crate::CatalogueEntry;
+
+/// Directory of the source `--quarantine-broken` moves broken downloads into.
+pub const BROKEN_DIR: &str = "_broken";
+
+/// The first media file of `entry` smaller than `min_size` and its size. Downloads that failed
+/// mid-write leave such files behind, linking them only gives unplayable episodes.
+pub fn find(entry: &CatalogueEntry, min_size: u64) -> Option<(&Path, u64)> {
+    entry.get_media().find_map(|path| {
+        let size = std::fs::metadata(path).ok()?.len();
+        (size < min_size).then_some((path.as_path(), size))
+    })
+}
+
+/// Moves the media and sidecars of `entry` into `_broken` of `source`, keeping their path below
+/// it. yt-dlp then no longer finds the files and downloads the video again.
+pub fn quarantine(entry: &CatalogueEntry, source: &Path) -> anyhow::Result<()> {
+    let quarantine = source.join(BROKEN_DIR);
+    std::fs::create_dir_all(&quarantine).with_context(|| format!("Can't create {quarantine:?}"))?;
+    // Keeps the quarantine out of the next scans.
+    let marker = quarantine.join(crate::IGNORE_MARKERS[0]);
+    if !marker.exists() {
+        File::create(&marker).with_context(|| format!("Can't create {marker:?}"))?;
+    }
+
+    for path in &entry.path {
+        let relative = path
+            .strip_prefix(source)
+            .unwrap_or(Path::new(path.file_name().unwrap_or_default()));
+        let target = quarantine.join(relative);
+        if let Some(dir) = target.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Can't create {dir:?}"))?;
+        }
+        std::fs::rename(path, &target)
+            .with_context(|| format!("Can't move {path:?} to {target:?}"))?;
+    }
+
+    Ok(())
+}
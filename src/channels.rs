@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::{VideoCatalogue, config::Config, stats::human_size};
+
+/// A channel found in the source.
+pub struct ChannelInfo<'a> {
+    pub name: &'a str,
+    pub videos: usize,
+    pub first: NaiveDateTime,
+    pub last: NaiveDateTime,
+    /// Size of the media files
+    pub size: u64,
+    /// Whether the config already has a section for the channel
+    pub configured: bool,
+}
+
+/// Every channel of the catalogue, ordered by name.
+pub fn collect<'a>(cat: &'a VideoCatalogue, config: &Config) -> Vec<ChannelInfo<'a>> {
+    cat.by_channel()
+        .into_iter()
+        .map(|(name, entries)| ChannelInfo {
+            name,
+            videos: entries.len(),
+            first: entries.iter().map(|e| e.date).min().unwrap_or_default(),
+            last: entries.iter().map(|e| e.date).max().unwrap_or_default(),
+            size: entries
+                .iter()
+                .flat_map(|e| e.get_media())
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|meta| meta.len())
+                .sum(),
+            configured: config.channels.contains_key(name),
+        })
+        .collect()
+}
+
+pub fn print(channels: &[ChannelInfo]) {
+    let width = channels
+        .iter()
+        .map(|c| c.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Channel".len());
+
+    println!(
+        "{:<width$}  {:>6}  {:<10}  {:<10}  {:>10}  Config",
+        "Channel", "Videos", "First", "Last", "Size"
+    );
+    for channel in channels {
+        let row = format!(
+            "{:<width$}  {:>6}  {:<10}  {:<10}  {:>10}  {}",
+            channel.name,
+            channel.videos,
+            channel.first.format("%Y-%m-%d"),
+            channel.last.format("%Y-%m-%d"),
+            human_size(channel.size),
+            if channel.configured { "yes" } else { "" }
+        );
+        println!("{}", row.trim_end());
+    }
+    println!(
+        "{} channels, {} videos, {}",
+        channels.len(),
+        channels.iter().map(|c| c.videos).sum::<usize>(),
+        human_size(channels.iter().map(|c| c.size).sum())
+    );
+}
+
+/// A section of `channels` in the config with every setting left at its default.
+#[derive(Serialize)]
+struct Skeleton {
+    mode: &'static str,
+    kind: Option<()>,
+    seasons: Option<()>,
+    numbering: Option<()>,
+    season_gaps: Option<()>,
+    title_rules: [(); 0],
+    target: Option<()>,
+}
+
+#[derive(Serialize)]
+struct ConfigSkeleton<'a> {
+    channels: BTreeMap<&'a str, Skeleton>,
+}
+
+/// Prints a `channels` object for the config with a section for each channel it doesn't have
+/// one for yet.
+pub fn print_config(channels: &[ChannelInfo]) -> anyhow::Result<()> {
+    let sections = channels
+        .iter()
+        .filter(|c| !c.configured)
+        .map(|c| {
+            let skeleton = Skeleton {
+                mode: "show",
+                kind: None,
+                seasons: None,
+                numbering: None,
+                season_gaps: None,
+                title_rules: [],
+                target: None,
+            };
+            (c.name, skeleton)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&ConfigSkeleton { channels: sections })?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    VideoJson,
+    config::{ChannelConfig, ChannelMode},
+};
+
+/// What kind of content a video is, guessed from its metadata or set per channel in the config.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentKind {
+    /// A regular video
+    Episode,
+    /// A YouTube Short
+    Short,
+    /// Recording of a live stream or a premiere
+    Livestream,
+    MusicVideo,
+    /// Feature length film
+    Movie,
+}
+
+impl ContentKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentKind::Episode => "episode",
+            ContentKind::Short => "short",
+            ContentKind::Livestream => "livestream",
+            ContentKind::MusicVideo => "music-video",
+            ContentKind::Movie => "movie",
+        }
+    }
+}
+
+/// Titles of official music releases.
+static MUSIC_VIDEO_TITLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)official\s+(music\s+)?video|official\s+audio|lyric\s+video|\(audio\)|\bMV\b")
+        .unwrap()
+});
+
+/// Films are at least this long, in seconds.
+const MOVIE_DURATION: f64 = 70.0 * 60.0;
+
+#[derive(Clone, Default)]
+pub struct Classifier {
+    /// Kinds forced for every video of a channel
+    channels: HashMap<String, ContentKind>,
+}
+
+impl Classifier {
+    pub fn new(channels: &HashMap<String, ChannelConfig>) -> Self {
+        let channels = channels
+            .iter()
+            .filter_map(|(name, channel)| {
+                let kind = match (channel.kind, channel.mode) {
+                    (Some(kind), _) => kind,
+                    (None, ChannelMode::MusicVideo) => ContentKind::MusicVideo,
+                    (None, ChannelMode::Show) => return None,
+                };
+                Some((name.clone(), kind))
+            })
+            .collect();
+
+        Self { channels }
+    }
+
+    pub fn classify(&self, json: &VideoJson) -> ContentKind {
+        self.explain(json).0
+    }
+
+    /// The kind of a video and why. Shorts (by URL or `#shorts`) are always shorts, even in
+    /// channels with a kind in the config, as they are skipped. Then the channel config wins, then
+    /// in order: live stream recordings, music videos (Music category with an official release
+    /// title) and movies (Film & Animation or Movies, longer than 70 minutes).
+    pub fn explain(&self, json: &VideoJson) -> (ContentKind, &'static str) {
+        if json.is_short() {
+            return (
+                ContentKind::Short,
+                "downloaded from the channel's shorts tab",
+            );
+        }
+        if json.title.to_lowercase().contains("#shorts") {
+            return (ContentKind::Short, "#shorts in the title");
+        }
+        if let Some(kind) = self.channels.get(json.channel_name()) {
+            return (*kind, "set for the channel in the config");
+        }
+
+        let in_category = |name: &str| json.categories.iter().any(|c| c == name);
+
+        if json.was_live == Some(true)
+            || matches!(
+                json.live_status.as_deref(),
+                Some("was_live" | "is_live" | "post_live")
+            )
+        {
+            (ContentKind::Livestream, "recorded from a live stream")
+        } else if in_category("Music") && MUSIC_VIDEO_TITLE.is_match(&json.title) {
+            (
+                ContentKind::MusicVideo,
+                "Music category and an official release title",
+            )
+        } else if (in_category("Film & Animation") || in_category("Movies"))
+            && json.duration.is_some_and(|d| d >= MOVIE_DURATION)
+        {
+            (
+                ContentKind::Movie,
+                "film category and longer than 70 minutes",
+            )
+        } else {
+            (ContentKind::Episode, "no other kind matched")
+        }
+    }
+}
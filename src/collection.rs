@@ -0,0 +1,131 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+use crate::{
+    BuilderOptions, CatalogueEntry, VideoCatalogue,
+    events::Event,
+    manifest::Manifest,
+    nfo::escape,
+    plan::Operation,
+    term::{self, Color},
+};
+
+/// Writes an Emby/Jellyfin boxset (`<playlist> [boxset]/collection.xml`) into `dir` for every
+/// playlist of the catalogue, listing the media files the manifest places in `target`. Returns
+/// how many collections were written or updated.
+pub fn write(
+    catalogue: &VideoCatalogue,
+    target: &Path,
+    manifest: &Manifest,
+    dir: &Path,
+    options: &BuilderOptions,
+) -> anyhow::Result<usize> {
+    let mut playlists = BTreeMap::<&str, Vec<&CatalogueEntry>>::new();
+    for entry in catalogue.entries() {
+        if let Some(key) = entry.json.playlist_key() {
+            playlists.entry(key).or_default().push(entry);
+        }
+    }
+
+    let mut written = 0;
+    for (key, mut members) in playlists {
+        members.sort_by(|a, b| {
+            let position = |entry: &CatalogueEntry| {
+                let position = entry.json.playlist_position();
+                (position.is_none(), position)
+            };
+            position(a)
+                .cmp(&position(b))
+                .then_with(|| options.sort.videos(a, b))
+        });
+
+        let paths = members
+            .iter()
+            .flat_map(|entry| media_links(entry, target, manifest))
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            continue;
+        }
+
+        let name = members
+            .iter()
+            .find_map(|entry| entry.json.playlist.as_deref())
+            .unwrap_or(key);
+        let path = dir
+            .join(format!(
+                "{} [boxset]",
+                options.naming.normalization.apply(&name.replace('/', "_"))
+            ))
+            .join("collection.xml");
+        let content = render(name, &paths);
+
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+            continue;
+        }
+
+        written += 1;
+        options.events.emit(Event::Collection { path: &path });
+
+        if options.verbose {
+            term::say(Color::Green, "Writing", path.display());
+        }
+
+        if options.dry_run {
+            options.plan.record(|| Operation::Write { path, content });
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+    }
+
+    Ok(written)
+}
+
+/// Subdirectory of `--collections` the boxsets of a target go to when there are several targets,
+/// named after its path (`/mnt/media/YouTube` becomes `mnt_media_YouTube`).
+pub fn dir_name(target: &Path) -> String {
+    target
+        .to_string_lossy()
+        .split(['/', ':'])
+        .filter(|part| !part.is_empty())
+        .join("_")
+}
+
+/// Media files of the episode in the target, in the order the manifest lists them.
+pub fn media_links(entry: &CatalogueEntry, target: &Path, manifest: &Manifest) -> Vec<PathBuf> {
+    let media = entry.get_media().collect_vec();
+
+    manifest
+        .links
+        .iter()
+        .filter(|(_, known)| known.id == entry.json.id && media.contains(&&known.source))
+        .map(|(link, _)| target.join(link))
+        .collect()
+}
+
+fn render(name: &str, paths: &[PathBuf]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n");
+    out.push_str("<Item>\n");
+    let _ = writeln!(out, "  <LocalTitle>{}</LocalTitle>", escape(name));
+    out.push_str("  <CollectionItems>\n");
+    for path in paths {
+        let _ = writeln!(
+            out,
+            "    <CollectionItem>\n      <Path>{}</Path>\n    </CollectionItem>",
+            escape(&path.to_string_lossy())
+        );
+    }
+    out.push_str("  </CollectionItems>\n");
+    out.push_str("</Item>\n");
+
+    out
+}
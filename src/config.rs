@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    Numbering, SeasonGaps, SeasonStrategy,
+    classify::ContentKind,
+    error::OrganizerError,
+    media_server::MediaServer,
+    naming::{Normalization, TitleRule},
+    route::Route,
+};
+
+/// Settings read from the JSON config file.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Used when `--target` isn't given
+    pub target: Option<PathBuf>,
+    /// Used when `--library-subdir` isn't given
+    pub library_subdir: Option<PathBuf>,
+    /// Used when `--max-operations` isn't given
+    pub max_operations: Option<usize>,
+    /// Used when `--max-delete` isn't given
+    pub max_delete: Option<usize>,
+    /// Used when `--layout` isn't given
+    pub layout: Option<String>,
+    /// Used when `--unicode-normalization` isn't given
+    pub unicode_normalization: Option<Normalization>,
+    pub notifications: Vec<Notification>,
+    /// Regex replacements applied to the titles of every channel
+    pub title_rules: Vec<TitleRule>,
+    /// Appended to the `--route` options
+    pub routes: Vec<Route>,
+    /// Overrides keyed by channel name
+    pub channels: HashMap<String, ChannelConfig>,
+    /// Emby or Jellyfin server the watch state of moved episodes is carried over on, with
+    /// `--transfer-watched`
+    pub media_server: Option<MediaServer>,
+    /// The file as read, empty without one
+    #[serde(skip)]
+    pub contents: Vec<u8>,
+}
+
+/// Settings of a single channel, unset fields fall back to the command line.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelConfig {
+    pub mode: ChannelMode,
+    /// Content kind of every video of the channel, instead of guessing it
+    pub kind: Option<ContentKind>,
+    pub seasons: Option<SeasonStrategy>,
+    pub numbering: Option<Numbering>,
+    pub season_gaps: Option<SeasonGaps>,
+    /// Added to every episode number outside season 0, so a library numbered by hand before
+    /// continues where it stopped (`56` makes the first episode E57)
+    pub episode_offset: usize,
+    /// Added to every season number but 0
+    pub season_offset: usize,
+    /// Season the channel becomes with `--single-show`, so adding a channel doesn't renumber
+    /// the others
+    pub show_season: Option<usize>,
+    /// Applied after the global `title_rules`
+    pub title_rules: Vec<TitleRule>,
+    /// Target root of the channel instead of the main one, e.g. a separate Emby library. Taken
+    /// before the `routes`
+    pub target: Option<PathBuf>,
+}
+
+/// How the videos of a channel are presented to the media server.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChannelMode {
+    /// A TV show with seasons and episodes
+    #[default]
+    Show,
+    /// The channel is an artist: `Artist/Artist - Title (Year)` with music video NFOs, for Emby's
+    /// Music Videos libraries
+    MusicVideo,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Notification {
+    /// Raw JSON run report, same as `--webhook-url`
+    Webhook {
+        url: String,
+    },
+    Discord {
+        webhook_url: String,
+    },
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+    },
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(base.join("ytdlp-organise").join("config.json"))
+    }
+
+    /// Reads the config from `path`, or from the default location when it exists.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Config::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound && !required => {
+                return Ok(Config::default());
+            }
+            Err(err) => return Err(OrganizerError::io(&path)(err).into()),
+        };
+        let mut config: Config =
+            serde_json::from_slice(&contents).map_err(|err| OrganizerError::ParseError {
+                path: path.clone(),
+                reason: err.to_string(),
+            })?;
+        config.contents = contents;
+
+        Ok(config)
+    }
+
+    /// Every route in the order they're tried: the `target` of a channel first, so it overrides
+    /// any route its videos would match, then `--route` and the `routes` of the config.
+    pub fn routes(&self, cli: &[Route]) -> Vec<Route> {
+        let mut channel_routes = self
+            .channels
+            .iter()
+            .filter_map(|(name, channel)| Some(Route::channel(name, channel.target.clone()?)))
+            .collect::<Vec<_>>();
+        channel_routes.sort_by(|a, b| a.target.cmp(&b.target));
+
+        channel_routes
+            .into_iter()
+            .chain(cli.iter().cloned())
+            .chain(self.routes.iter().cloned())
+            .collect()
+    }
+}
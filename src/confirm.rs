@@ -0,0 +1,41 @@
+use std::{
+    io::{BufRead, IsTerminal, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether deleting and overwriting goes ahead without asking, from `--yes` or an `[a]ll` answer.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+pub fn assume_yes() {
+    ASSUME_YES.store(true, Ordering::Relaxed);
+}
+
+/// Asks on the terminal before something is deleted or overwritten. Runs that aren't interactive
+/// (cron, yt-dlp's `--exec`, the daemon) and runs with `--yes` go ahead without asking; answering
+/// `a` does so for the rest of the run.
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    if ASSUME_YES.load(Ordering::Relaxed) || !std::io::stdin().is_terminal() {
+        return Ok(true);
+    }
+
+    let mut stderr = std::io::stderr().lock();
+    loop {
+        write!(stderr, "{question} [y]es, [N]o, [a]ll: ")?;
+        stderr.flush()?;
+
+        let mut answer = String::new();
+        if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(false);
+        }
+
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "" | "n" | "no" => return Ok(false),
+            "a" | "all" => {
+                assume_yes();
+                return Ok(true);
+            }
+            _ => continue,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    cell::Cell,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use anyhow::bail;
+use clap::ValueEnum;
+
+/// What happens to an episode whose files would land on paths already taken by something else.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Resolution {
+    /// Leave the existing files alone and only place files whose paths are free
+    #[default]
+    Keep,
+    /// Remove the existing files and place the episode instead
+    Replace,
+    /// Place the episode under a free name (`Title (2)`)
+    Rename,
+    /// Don't place the episode at all
+    Skip,
+    /// Stop the run with an error
+    Fail,
+}
+
+/// Decides how conflicts are resolved, either with a fixed policy or by asking on the terminal.
+/// Clones share the "apply to all" answer.
+#[derive(Clone, Default)]
+pub struct Conflicts {
+    policy: Resolution,
+    prompt: bool,
+    all: Rc<Cell<Option<Resolution>>>,
+}
+
+impl Conflicts {
+    pub fn new(policy: Resolution, prompt: bool) -> Self {
+        Self {
+            policy,
+            prompt,
+            all: Rc::default(),
+        }
+    }
+
+    /// Whether conflicts are resolved by asking on the terminal.
+    pub fn prompts(&self) -> bool {
+        self.prompt
+    }
+
+    /// Picks the resolution for an episode, `reasons` describe each conflicting path.
+    pub fn resolve(&self, title: &str, reasons: &[String]) -> anyhow::Result<Resolution> {
+        if !self.prompt {
+            return Ok(self.policy);
+        }
+        if let Some(resolution) = self.all.get() {
+            return Ok(resolution);
+        }
+
+        let mut stderr = std::io::stderr().lock();
+        writeln!(stderr, "Conflict placing {title:?}:")?;
+        for reason in reasons {
+            writeln!(stderr, "  {reason}")?;
+        }
+
+        loop {
+            write!(
+                stderr,
+                "[k]eep existing, [r]eplace, re[n]ame, [s]kip (uppercase applies to all): "
+            )?;
+            stderr.flush()?;
+
+            let mut answer = String::new();
+            if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+                bail!("No answer to the conflict prompt, stdin was closed");
+            }
+
+            let answer = answer.trim();
+            let resolution = match answer.to_ascii_lowercase().as_str() {
+                "k" => Resolution::Keep,
+                "r" => Resolution::Replace,
+                "n" => Resolution::Rename,
+                "s" => Resolution::Skip,
+                _ => continue,
+            };
+
+            if answer.chars().all(|c| c.is_ascii_uppercase()) {
+                self.all.set(Some(resolution));
+            }
+
+            return Ok(resolution);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, anyhow};
+use serde::Serialize;
+
+use crate::{
+    schedule::Schedule,
+    signals,
+    summary::Report,
+    term::{self, Color},
+};
+
+/// Time between runs: seconds or a number with an `s`, `m`, `h` or `d` unit.
+#[derive(Clone, Copy)]
+pub struct Interval(pub Duration);
+
+impl FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| anyhow!("Invalid interval {s:?}"))?;
+
+        let seconds = match unit {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            other => return Err(anyhow!("Unknown interval unit {other:?}")),
+        };
+
+        let seconds = number
+            .checked_mul(seconds)
+            .ok_or_else(|| anyhow!("Interval {s:?} is too long"))?;
+        if seconds == 0 {
+            return Err(anyhow!("The interval can't be 0"));
+        }
+
+        Ok(Interval(Duration::from_secs(seconds)))
+    }
+}
+
+/// When the daemon runs.
+pub enum Timing {
+    /// Right away and then again after every interval
+    Every(Duration),
+    /// At the times matching a cron expression
+    Cron(Schedule),
+}
+
+#[derive(Serialize)]
+struct LastRun {
+    started: String,
+    finished: String,
+    duration_seconds: f64,
+    report: serde_json::Value,
+}
+
+/// What the status endpoint reports, updated after every run.
+#[derive(Serialize)]
+struct Status {
+    started: String,
+    running: bool,
+    runs: u64,
+    /// Videos found in the source by the last run
+    catalogue: usize,
+    /// Videos of the last run's catalogue not placed in any target
+    pending: usize,
+    /// Errors of the last run
+    errors: Vec<String>,
+    last_run: Option<String>,
+    next_run: Option<String>,
+    #[serde(skip)]
+    last: Option<LastRun>,
+}
+
+/// Calls `run` as `timing` says until SIGINT or SIGTERM, serving the status over HTTP on
+/// `listen` (`/status`, `/healthz` and `/last-run`). Failed runs are reported and retried on the
+/// next tick.
+pub fn run(
+    timing: Timing,
+    listen: Option<SocketAddr>,
+    mut run: impl FnMut() -> (Report, anyhow::Result<()>),
+) -> anyhow::Result<()> {
+    let status = Arc::new(Mutex::new(Status {
+        started: now(),
+        running: false,
+        runs: 0,
+        catalogue: 0,
+        pending: 0,
+        errors: Vec::new(),
+        last_run: None,
+        next_run: None,
+        last: None,
+    }));
+
+    if let Some(addr) = listen {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("Can't listen on {addr}"))?;
+        let status = status.clone();
+        std::thread::spawn(move || serve(listener, status));
+    }
+
+    loop {
+        if let Timing::Cron(schedule) = &timing {
+            let next = schedule
+                .next_after(chrono::Local::now())
+                .context("The schedule never matches")?;
+            status.lock().unwrap().next_run = Some(next.to_rfc3339());
+            if !signals::sleep((next - chrono::Local::now()).to_std().unwrap_or_default()) {
+                return Ok(());
+            }
+        }
+
+        let started = now();
+        let clock = Instant::now();
+        status.lock().unwrap().running = true;
+
+        let (report, result) = run();
+        if let Err(err) = &result {
+            let message = format!("Run failed: {err:#}");
+            term::error(Color::Red, message);
+        }
+
+        // The last report is only served over HTTP, one that can't be serialized mustn't stop
+        // the daemon.
+        let report_json = serde_json::to_value(&report).unwrap_or_else(|err| {
+            let message = format!("Can't serialize the run report: {err}");
+            term::error(Color::Red, &message);
+            serde_json::json!({ "error": message })
+        });
+
+        let mut status = status.lock().unwrap();
+        status.running = false;
+        status.runs += 1;
+        status.catalogue = report.catalogue;
+        status.pending = report.pending;
+        status.errors = report.errors.clone();
+        status.last_run = Some(now());
+        status.next_run = match &timing {
+            Timing::Every(interval) => chrono::TimeDelta::from_std(*interval)
+                .ok()
+                .and_then(|delta| chrono::Local::now().checked_add_signed(delta))
+                .map(|next| next.to_rfc3339()),
+            Timing::Cron(schedule) => schedule
+                .next_after(chrono::Local::now())
+                .map(|next| next.to_rfc3339()),
+        };
+        status.last = Some(LastRun {
+            started,
+            finished: now(),
+            duration_seconds: clock.elapsed().as_secs_f64(),
+            report: report_json,
+        });
+        drop(status);
+
+        if signals::interrupted() {
+            return Ok(());
+        }
+        if let Timing::Every(interval) = timing
+            && !signals::sleep(interval)
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+fn serve(listener: TcpListener, status: Arc<Mutex<Status>>) {
+    for stream in listener.incoming().flatten() {
+        if let Err(err) = respond(stream, &status) {
+            let message = format!("Status request failed: {err}");
+            term::error(Color::Red, message);
+        }
+    }
+}
+
+fn respond(stream: TcpStream, status: &Mutex<Status>) -> anyhow::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Headers aren't used, but have to be read before answering.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let (code, body) = {
+        let status = status.lock().unwrap();
+        match path {
+            "/healthz" => ("200 OK", "{\"ok\":true}".to_string()),
+            "/status" => ("200 OK", serde_json::to_string(&*status)?),
+            "/last-run" => match &status.last {
+                Some(last) => ("200 OK", serde_json::to_string(last)?),
+                None => (
+                    "404 Not Found",
+                    "{\"error\":\"no run finished yet\"}".to_string(),
+                ),
+            },
+            _ => ("404 Not Found", "{\"error\":\"not found\"}".to_string()),
+        }
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {code}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+
+    Ok(())
+}
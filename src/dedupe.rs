@@ -0,0 +1,134 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+
+use crate::{VideoCatalogue, stats::human_size, term};
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Dedupe {
+    /// Only list byte-identical media files
+    Report,
+    /// Place identical files in the target once and hard link (or symlink) the rest to it
+    Link,
+}
+
+/// Groups of byte-identical media files found in the catalogue, plus the target path each group
+/// was first placed at in each target root during the current run.
+#[derive(Default)]
+pub struct DuplicateIndex {
+    groups: Vec<Vec<PathBuf>>,
+    group_of: HashMap<PathBuf, usize>,
+    placed: RefCell<HashMap<(PathBuf, usize), PathBuf>>,
+}
+
+impl DuplicateIndex {
+    /// Hashes every media file sharing its size with another one.
+    pub fn build(cat: &VideoCatalogue, verbose: bool) -> anyhow::Result<Self> {
+        let by_size = cat
+            .entries()
+            .flat_map(|e| e.get_media())
+            .unique()
+            .filter_map(|path| Some((std::fs::metadata(path).ok()?.len(), path)))
+            .into_group_map();
+
+        let candidates = by_size
+            .into_iter()
+            .filter(|(size, paths)| *size > 0 && paths.len() > 1)
+            .flat_map(|(_, paths)| paths)
+            .collect::<Vec<_>>();
+
+        if verbose {
+            term::print(format_args!("Hashing {} candidate files", candidates.len()));
+        }
+
+        let mut by_hash = HashMap::<_, Vec<PathBuf>>::new();
+        for path in candidates {
+            by_hash
+                .entry(hash_file(path)?)
+                .or_default()
+                .push(path.clone());
+        }
+
+        let mut groups = by_hash
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .map(|mut paths| {
+                paths.sort();
+                paths
+            })
+            .collect::<Vec<_>>();
+        groups.sort();
+
+        let mut index = DuplicateIndex::default();
+        for (group, paths) in groups.iter().enumerate() {
+            for path in paths {
+                index.group_of.insert(path.clone(), group);
+            }
+        }
+        index.groups = groups;
+
+        Ok(index)
+    }
+
+    pub fn report(&self) {
+        let mut wasted = 0;
+
+        for group in &self.groups {
+            let size = std::fs::metadata(&group[0]).map_or(0, |m| m.len());
+            wasted += size * (group.len() as u64 - 1);
+
+            println!("Identical files ({}):", human_size(size));
+            for path in group {
+                println!("  {path:?}");
+            }
+        }
+
+        println!(
+            "{} groups of identical files, {} could be saved",
+            self.groups.len(),
+            human_size(wasted)
+        );
+    }
+
+    /// First file of the group `source` belongs to, every member of a group links to it.
+    pub fn canonical(&self, source: &Path) -> Option<&PathBuf> {
+        self.group_of
+            .get(source)
+            .map(|&group| &self.groups[group][0])
+    }
+
+    /// Target an identical file was already placed at in `root` during this run. Files of other
+    /// roots are never offered, a mirror mustn't share inodes with (or fail to link across
+    /// filesystems to) another library.
+    pub fn placed(&self, source: &Path, root: &Path) -> Option<PathBuf> {
+        let group = *self.group_of.get(source)?;
+        self.placed
+            .borrow()
+            .get(&(root.to_path_buf(), group))
+            .cloned()
+    }
+
+    pub fn mark_placed(&self, source: &Path, root: &Path, target: &Path) {
+        if let Some(&group) = self.group_of.get(source) {
+            self.placed
+                .borrow_mut()
+                .entry((root.to_path_buf(), group))
+                .or_insert_with(|| target.to_path_buf());
+        }
+    }
+}
+
+pub fn hash_file(path: &Path) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,292 @@
+use std::{
+    ffi::CString,
+    fs::File,
+    io::ErrorKind,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+
+use crate::{Cli, LinkMode, config::Config, manifest::Manifest, remote::Remote, state::State};
+
+const PROBE_FILE_NAME: &str = ".ytdlp-organise.doctor";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Ok,
+    Warning,
+    Problem,
+}
+
+/// Collects the outcome of the checks and prints each as it's made.
+#[derive(Default)]
+struct Findings {
+    warnings: usize,
+    problems: usize,
+}
+
+impl Findings {
+    fn report(&mut self, status: Status, message: impl AsRef<str>, fix: Option<&str>) {
+        let label = match status {
+            Status::Ok => "ok",
+            Status::Warning => {
+                self.warnings += 1;
+                "warning"
+            }
+            Status::Problem => {
+                self.problems += 1;
+                "problem"
+            }
+        };
+
+        println!("{label:<8} {}", message.as_ref());
+        if let Some(fix) = fix {
+            println!("{:<8} fix: {fix}", "");
+        }
+    }
+}
+
+/// Checks the source, the config and every target for common setup problems and prints how to
+/// fix them. Fails when any check found a problem that would break a run.
+pub fn run(cli: &Cli) -> anyhow::Result<()> {
+    let mut findings = Findings::default();
+
+    check_source(cli, &mut findings);
+
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => {
+            findings.report(Status::Ok, "Config loaded", None);
+            config
+        }
+        Err(err) => {
+            findings.report(
+                Status::Problem,
+                format!("{err:#}"),
+                Some("Fix the JSON or the unknown keys it points at"),
+            );
+            Config::default()
+        }
+    };
+
+    let mut targets = cli.target.clone();
+    if targets.is_empty() {
+        targets.extend(config.target.clone());
+    }
+    for route in config.routes(&cli.route) {
+        if !targets.contains(&route.target) {
+            targets.push(route.target);
+        }
+    }
+
+    if targets.is_empty() {
+        findings.report(
+            Status::Warning,
+            "No target, only `list`, `stats` and `explain` can run",
+            Some("Pass --target or set \"target\" in the config"),
+        );
+    }
+    for target in &targets {
+        check_target(cli, target, &mut findings);
+    }
+
+    println!(
+        "{} problems, {} warnings",
+        findings.problems, findings.warnings
+    );
+    if findings.problems > 0 {
+        bail!("The setup has problems, see the fixes above");
+    }
+
+    Ok(())
+}
+
+fn check_source(cli: &Cli, findings: &mut Findings) {
+    let source = &cli.source;
+
+    match std::fs::read_dir(source) {
+        Ok(_) => findings.report(Status::Ok, format!("SOURCE {source:?} is readable"), None),
+        Err(err) => findings.report(
+            Status::Problem,
+            format!("Can't read SOURCE {source:?}: {err}"),
+            Some("Check the path and that this user may list it"),
+        ),
+    }
+
+    // Symlinks store the path they were given, a relative one resolves against the link's own
+    // directory in the target.
+    if cli.link_mode == LinkMode::Symlink && source.is_relative() {
+        findings.report(
+            Status::Problem,
+            format!(
+                "SOURCE {source:?} is relative, the symlinks placed in the target would dangle"
+            ),
+            Some("Pass SOURCE as an absolute path"),
+        );
+    }
+}
+
+fn check_target(cli: &Cli, target: &Path, findings: &mut Findings) {
+    match Remote::parse(target) {
+        Ok(None) => {}
+        Ok(Some(remote)) => {
+            findings.report(
+                Status::Ok,
+                format!("Target {target:?} is uploaded over SFTP, checking its staging directory"),
+                None,
+            );
+            return check_target(cli, &remote.staging_dir(), findings);
+        }
+        Err(err) => {
+            findings.report(
+                Status::Problem,
+                format!("{err:#}"),
+                Some("Use ssh://[user@]host[:port]/path"),
+            );
+            return;
+        }
+    }
+
+    if !target.exists() {
+        let parent = target
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.exists())
+            .unwrap_or(Path::new("."));
+        check_writable(target, parent, !cli.check_only, findings);
+        return;
+    }
+
+    check_writable(target, target, !cli.check_only, findings);
+    check_filesystems(cli, target, findings);
+
+    for path in [Manifest::path(target), State::path(target)] {
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        if tmp.exists() {
+            findings.report(
+                Status::Warning,
+                format!("{tmp:?} was left behind by an interrupted run"),
+                Some("Delete it once no other run is using the target"),
+            );
+        }
+    }
+
+    match Manifest::load(target) {
+        Ok(_) => findings.report(Status::Ok, format!("Manifest of {target:?} is valid"), None),
+        Err(err) => findings.report(
+            Status::Problem,
+            format!(
+                "Can't read the manifest {:?}: {err}",
+                Manifest::path(target)
+            ),
+            Some(
+                "Restore it from a backup (the daily copies `.1` to `.7` next to it, or `state \
+                 import`) or move it aside, the next run records the files it places again but can no longer prune the ones it placed before",
+            ),
+        ),
+    }
+
+    match State::load(target) {
+        Ok(_) => findings.report(Status::Ok, format!("State of {target:?} is valid"), None),
+        Err(err) => findings.report(
+            Status::Problem,
+            format!("Can't read the state {:?}: {err}", State::path(target)),
+            Some(
+                "Restore it from a backup (the daily copies `.1` to `.7` next to it, or `state \
+                 import`) or move it aside, `--stable-numbering` then assigns numbers afresh and may renumber episodes",
+            ),
+        ),
+    }
+}
+
+/// Creates and removes a file in `dir` to find out whether the target can be written to, or
+/// without `probe` (`--check-only`) asks the kernel instead.
+fn check_writable(target: &Path, dir: &Path, probe: bool, findings: &mut Findings) {
+    let result = if probe {
+        let probe = dir.join(PROBE_FILE_NAME);
+        match File::create_new(&probe) {
+            Ok(_) => std::fs::remove_file(&probe),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(()),
+            Err(err) => Err(err),
+        }
+    } else {
+        access_writable(dir)
+    };
+
+    match result {
+        Ok(()) if dir == target => {
+            findings.report(Status::Ok, format!("Target {target:?} is writable"), None)
+        }
+        Ok(()) => findings.report(
+            Status::Ok,
+            format!("Target {target:?} doesn't exist yet, it will be created in {dir:?}"),
+            None,
+        ),
+        Err(err) => findings.report(
+            Status::Problem,
+            format!("Can't write to {dir:?}: {err}"),
+            Some("Give this user write access (chown/chmod) or pick another target"),
+        ),
+    }
+}
+
+fn access_writable(dir: &Path) -> std::io::Result<()> {
+    let c_path = CString::new(dir.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is NUL terminated.
+    if unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn check_filesystems(cli: &Cli, target: &Path, findings: &mut Findings) {
+    use std::os::unix::fs::MetadataExt;
+
+    let (Ok(source_meta), Ok(target_meta)) = (cli.source.metadata(), target.metadata()) else {
+        return;
+    };
+
+    if source_meta.dev() == target_meta.dev() {
+        findings.report(
+            Status::Ok,
+            format!("SOURCE and {target:?} are on the same filesystem"),
+            None,
+        );
+        return;
+    }
+
+    match cli.link_mode {
+        LinkMode::Symlink => findings.report(
+            Status::Warning,
+            format!(
+                "SOURCE and {target:?} are on different filesystems, the symlinks only resolve \
+                 where SOURCE is mounted at {:?} too",
+                cli.source
+            ),
+            Some(
+                "Mount SOURCE at the same path for the media server (mind container volumes) \
+                 or use --link-mode copy",
+            ),
+        ),
+        LinkMode::Reflink => findings.report(
+            Status::Warning,
+            format!(
+                "SOURCE and {target:?} are on different filesystems, reflinks can't cross them \
+                 and every file will be copied"
+            ),
+            Some("Keep both on one btrfs or XFS filesystem, or use --link-mode copy"),
+        ),
+        LinkMode::Copy => findings.report(
+            Status::Ok,
+            format!("SOURCE and {target:?} are on different filesystems, files are copied"),
+            None,
+        ),
+    }
+}
+
+#[cfg(not(unix))]
+fn check_filesystems(_cli: &Cli, _target: &Path, _findings: &mut Findings) {}
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+/// Failures worth telling apart programmatically, anything else bails through `anyhow` and is
+/// reported as `other`.
+#[derive(thiserror::Error, Debug)]
+pub enum OrganizerError {
+    /// A malformed info.json, config or date
+    #[error("Can't parse {path:?}: {reason}")]
+    ParseError { path: PathBuf, reason: String },
+    /// An info.json without a field every video needs
+    #[error("{path:?} has no {field:?}")]
+    MissingField { path: PathBuf, field: String },
+    #[error("Can't access {path:?}")]
+    IoError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// An episode's files would land on paths taken by something else, with `--on-conflict fail`
+    #[error("Conflict placing {title:?}: {}", reasons.join(", "))]
+    CollisionError { title: String, reasons: Vec<String> },
+    /// The manifest or numbering state in the target can't be read
+    #[error("Can't read {path:?}: {reason}")]
+    StateError { path: PathBuf, reason: String },
+}
+
+impl OrganizerError {
+    /// Wraps an I/O error with the path it happened on, for `map_err`.
+    pub fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        |source| OrganizerError::IoError {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Sorts a deserialization error of `path` into a missing field or a parse error.
+    pub fn json(path: &Path, err: serde_json::Error) -> Self {
+        let message = err.to_string();
+        let field = message
+            .strip_prefix("missing field `")
+            .and_then(|rest| rest.split_once('`'))
+            .map(|(field, _)| field.to_string());
+
+        match field {
+            Some(field) => OrganizerError::MissingField {
+                path: path.to_path_buf(),
+                field,
+            },
+            None => OrganizerError::ParseError {
+                path: path.to_path_buf(),
+                reason: message,
+            },
+        }
+    }
+
+    /// Name of the variant as used in the JSON output.
+    pub fn category(&self) -> &'static str {
+        match self {
+            OrganizerError::ParseError { .. } => "parse",
+            OrganizerError::MissingField { .. } => "missing_field",
+            OrganizerError::IoError { .. } => "io",
+            OrganizerError::CollisionError { .. } => "collision",
+            OrganizerError::StateError { .. } => "state",
+        }
+    }
+
+    /// Category of the first `OrganizerError` in the chain of `err`, `other` if there is none.
+    pub fn category_of(err: &anyhow::Error) -> &'static str {
+        err.chain()
+            .find_map(|err| err.downcast_ref::<OrganizerError>())
+            .map_or("other", OrganizerError::category)
+    }
+}
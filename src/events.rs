@@ -0,0 +1,117 @@
+use std::{
+    cell::RefCell,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::classify::ContentKind;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line
+    Ndjson,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Parsed {
+        path: &'a Path,
+        id: &'a str,
+        channel: &'a str,
+        kind: ContentKind,
+    },
+    Skipped {
+        path: &'a Path,
+        reason: &'a str,
+    },
+    Directory {
+        path: &'a Path,
+    },
+    Linked {
+        source: &'a Path,
+        target: &'a Path,
+        mode: &'a str,
+    },
+    Artwork {
+        path: &'a Path,
+    },
+    Nfo {
+        path: &'a Path,
+    },
+    Collection {
+        path: &'a Path,
+    },
+    Playlist {
+        path: &'a Path,
+    },
+    Feed {
+        path: &'a Path,
+    },
+    Error {
+        message: String,
+        /// What went wrong, see `OrganizerError::category`
+        category: &'static str,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    time: String,
+    dry_run: bool,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Sink for machine readable progress events, a default (disabled) sink drops everything.
+#[derive(Clone, Default)]
+pub struct Events {
+    writer: Option<Rc<RefCell<Box<dyn Write>>>>,
+    dry_run: bool,
+}
+
+impl Events {
+    /// Opens the sink, events are appended to `file` or written to stdout when no file is given.
+    pub fn open(
+        format: Option<EventFormat>,
+        file: Option<&PathBuf>,
+        dry_run: bool,
+    ) -> anyhow::Result<Self> {
+        let Some(EventFormat::Ndjson) = format else {
+            return Ok(Self::default());
+        };
+
+        let writer: Box<dyn Write> = match file {
+            Some(path) => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+            None => Box::new(std::io::stdout()),
+        };
+
+        Ok(Self {
+            writer: Some(Rc::new(RefCell::new(writer))),
+            dry_run,
+        })
+    }
+
+    pub fn emit(&self, event: Event) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+
+        let record = Record {
+            time: chrono::Local::now().to_rfc3339(),
+            dry_run: self.dry_run,
+            event: &event,
+        };
+
+        let mut writer = writer.borrow_mut();
+        if let Ok(line) = serde_json::to_string(&record) {
+            let _ = writeln!(writer, "{line}");
+            let _ = writer.flush();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    BuilderOptions, CatalogueOptions, DirectoryBuilder, Episode, InfoJson, Library, Season,
+    SeasonedStructure, VideoCatalogue, VideoJson, classify::ContentKind, config::Config,
+    filename::Confidence, info_json_for, manifest::Manifest, read_info_json,
+};
+
+/// Prints how `video` (a path or a video id) is classified, which channel, season and episode it
+/// becomes and where its files would be placed.
+pub fn print(
+    video: &str,
+    catalogue: &VideoCatalogue,
+    libraries: &[Library],
+    options: &BuilderOptions,
+    config: &Config,
+    catalogue_options: &CatalogueOptions,
+) -> anyhow::Result<()> {
+    let json = if Path::new(video).exists() {
+        let path = info_json_for(Path::new(video));
+        let json = InfoJson::from_slice(&read_info_json(&path)?)
+            .with_context(|| format!("Can't load {path:?}"))?;
+        match json {
+            InfoJson::Video(json) => Some((path, *json)),
+            InfoJson::Playlist => bail!("{path:?} describes a playlist, not a video"),
+        }
+    } else {
+        None
+    };
+    let id = json.as_ref().map_or(video, |(_, json)| json.id.as_str());
+
+    let Some(entry) = catalogue.entries().find(|entry| entry.json.id == id) else {
+        let Some((path, json)) = &json else {
+            bail!("No video with the id {video:?} in the source");
+        };
+
+        print_video(path, json, catalogue_options);
+        if catalogue_options.classifier.classify(json) != ContentKind::Short {
+            println!("Not organized: the info.json isn't part of the scanned source");
+        }
+        return Ok(());
+    };
+
+    let info_json = entry.path.first().cloned().unwrap_or_default();
+    print_video(&info_json, &entry.json, catalogue_options);
+    match entry.guessed {
+        None => {}
+        Some(Confidence::High) => println!("Guessed:   id and upload date read from the file name"),
+        Some(Confidence::Low) => {
+            println!("Guessed:   the file name has no id or upload date, they were made up")
+        }
+    }
+
+    println!("Sidecars:");
+    for file in entry.path.iter().skip(1) {
+        println!("  {file:?}");
+    }
+
+    let placement = libraries.iter().find_map(|library| {
+        library.structure.iter().find_map(|channel| {
+            channel.seasons.iter().find_map(|season| {
+                let ep = season.videos.iter().find(|ep| ep.entry.json.id == id)?;
+                Some((library, channel, season, ep))
+            })
+        })
+    });
+    let Some((library, channel, season, ep)) = placement else {
+        println!("Not organized: filtered out of the structure");
+        return Ok(());
+    };
+
+    println!("Show:      {}", channel.channel_name);
+    if season.key.is_empty() {
+        println!("Season:    {}", season.number);
+    } else {
+        println!("Season:    {} ({})", season.number, season.key);
+    }
+    println!("Episode:   {}", ep.number);
+
+    let options = crate::channel_options(options, config, &channel.channel_name)?;
+    println!("Title:     {}", options.naming.title(entry));
+
+    let (root, manifest) = match &library.target {
+        Some(target) => (target.clone(), Manifest::load(target)?),
+        None => {
+            println!("Target:    none, pass --target to place the files");
+            (PathBuf::from("<target>"), Manifest::default())
+        }
+    };
+
+    // A structure of just this episode, placing it doesn't depend on the rest of the channel.
+    let single = SeasonedStructure {
+        channel_name: channel.channel_name.clone(),
+        seasons: vec![Season {
+            number: season.number,
+            key: season.key.clone(),
+            videos: vec![Episode {
+                number: ep.number,
+                entry,
+            }],
+        }],
+    };
+    let builder = DirectoryBuilder::new(&root, single, &options);
+    let (season, ep) = (
+        &builder.channel.seasons[0],
+        &builder.channel.seasons[0].videos[0],
+    );
+
+    println!("Files (before conflicts with the target are resolved):");
+    for (source, target) in builder.planned_files(season, ep, &manifest) {
+        match source {
+            Some(source) => println!("  {source:?} -> {target:?}"),
+            None => println!("  (generated) -> {target:?}"),
+        }
+    }
+
+    Ok(())
+}
+
+fn print_video(path: &Path, json: &VideoJson, options: &CatalogueOptions) {
+    let (kind, reason) = options.classifier.explain(json);
+
+    println!("Video:     {} {:?}", json.id, json.title);
+    println!("Info:      {path:?}");
+    println!("Channel:   {}", json.channel_name());
+    println!("Uploaded:  {}", json.upload_date);
+    if let Some(released) = &json.release_date {
+        let used = match json.premiered() {
+            true => ", dated by it as a premiere",
+            false => "",
+        };
+        println!("Released:  {released}{used}");
+    }
+    println!("Kind:      {} ({reason})", kind.name());
+    if kind == ContentKind::Short {
+        println!("Not organized: shorts are skipped");
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    BuilderOptions, CatalogueEntry, VideoCatalogue,
+    events::Event,
+    manifest::Manifest,
+    nfo::escape,
+    plan::Operation,
+    term::{self, Color},
+};
+
+/// How many of the most recently organized episodes the feed lists.
+const ITEMS: usize = 50;
+
+/// A media file placed in a target, listed in the feed by when it was placed there.
+pub struct FeedItem<'a> {
+    entry: &'a CatalogueEntry,
+    /// Where the file is on this machine, its modification time is when it was organized
+    local: PathBuf,
+    url: String,
+}
+
+/// Collects the feed items of the media files the manifest places in `target`. `name` is the
+/// target as the user gave it, the `ssh://` URL of a remote one.
+pub fn collect<'a>(
+    catalogue: &'a VideoCatalogue,
+    target: &Path,
+    name: &Path,
+    manifest: &Manifest,
+    items: &mut Vec<FeedItem<'a>>,
+) {
+    let by_id = catalogue
+        .entries()
+        .map(|entry| (entry.json.id.as_str(), entry))
+        .collect::<HashMap<_, _>>();
+
+    for (link, known) in &manifest.links {
+        let Some(entry) = by_id.get(known.id.as_str()) else {
+            continue;
+        };
+        if !entry.get_media().any(|media| *media == known.source) {
+            continue;
+        }
+
+        items.push(FeedItem {
+            entry,
+            local: target.join(link),
+            url: url(name, link),
+        });
+    }
+}
+
+/// Writes an RSS feed of the most recently organized episodes to `path`. Returns whether it was
+/// written or updated.
+pub fn write(path: &Path, items: Vec<FeedItem>, options: &BuilderOptions) -> anyhow::Result<bool> {
+    // Files not placed yet (in a dry run) would be placed now.
+    let now = SystemTime::now();
+    let mut items = items
+        .into_iter()
+        .map(|item| {
+            let organized = item
+                .local
+                .symlink_metadata()
+                .and_then(|meta| meta.modified())
+                .unwrap_or(now);
+            (DateTime::<Utc>::from(organized), item)
+        })
+        .collect::<Vec<_>>();
+    items.sort_by(|(a_time, a), (b_time, b)| {
+        b_time
+            .cmp(a_time)
+            .then_with(|| a.entry.json.id.cmp(&b.entry.json.id))
+            .then_with(|| a.url.cmp(&b.url))
+    });
+    // A video mirrored to several targets is listed once, at its newest copy.
+    let mut listed = HashSet::new();
+    items.retain(|(_, item)| listed.insert(item.entry.json.id.as_str()));
+    items.truncate(ITEMS);
+
+    let content = render(&items);
+    if std::fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(false);
+    }
+
+    options.events.emit(Event::Feed { path });
+
+    if options.verbose {
+        term::say(Color::Green, "Writing", path.display());
+    }
+
+    if options.dry_run {
+        options.plan.record(|| Operation::Write {
+            path: path.to_path_buf(),
+            content,
+        });
+        return Ok(true);
+    }
+
+    std::fs::write(path, content)?;
+
+    Ok(true)
+}
+
+fn render(items: &[(DateTime<Utc>, FeedItem)]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<rss version=\"2.0\">\n");
+    out.push_str("  <channel>\n");
+    out.push_str("    <title>Latest episodes</title>\n");
+    out.push_str("    <description>The most recently organized episodes</description>\n");
+    for (organized, item) in items {
+        let json = &item.entry.json;
+        out.push_str("    <item>\n");
+        let _ = writeln!(
+            out,
+            "      <title>{}</title>",
+            escape(&format!("{} - {}", json.channel_name(), json.title))
+        );
+        let _ = writeln!(out, "      <link>{}</link>", escape(&item.url));
+        let _ = writeln!(
+            out,
+            "      <guid isPermaLink=\"false\">{}</guid>",
+            escape(&item.url)
+        );
+        let _ = writeln!(out, "      <pubDate>{}</pubDate>", organized.to_rfc2822());
+        if let Some(description) = &json.description {
+            let _ = writeln!(
+                out,
+                "      <description>{}</description>",
+                escape(description)
+            );
+        }
+        out.push_str("    </item>\n");
+    }
+    out.push_str("  </channel>\n");
+    out.push_str("</rss>\n");
+
+    out
+}
+
+/// `file://` URL of a placed file, or the file under the URL of a remote target.
+fn url(name: &Path, link: &Path) -> String {
+    let name = name.to_string_lossy();
+    let link = link.to_string_lossy();
+    if name.contains("://") {
+        format!("{}/{}", name.trim_end_matches('/'), percent_encode(&link))
+    } else {
+        let path = Path::new(name.as_ref()).join(link.as_ref());
+        let path = std::path::absolute(&path).unwrap_or(path);
+        format!("file://{}", percent_encode(&path.to_string_lossy()))
+    }
+}
+
+/// Escapes everything but unreserved characters and `/` of a URL path.
+fn percent_encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => {
+                let _ = write!(out, "%{byte:02X}");
+            }
+        }
+    }
+
+    out
+}
@@ -0,0 +1,77 @@
+use std::sync::LazyLock;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::Serialize;
+
+/// How much of an entry organized without an info.json could be read from its file name.
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    /// The name carried both the video id and the upload date
+    High,
+    /// The id or the date had to be made up (from the file name and its modification time)
+    Low,
+}
+
+/// Output templates of yt-dlp (and youtube-dl before it) commonly used for archives, tried in
+/// order. YouTube ids are 11 characters of `[0-9A-Za-z_-]`. The flag marks templates where the id
+/// follows a plain dash, so any 11 letter word would pass for one.
+static TEMPLATES: LazyLock<Vec<(Regex, bool)>> = LazyLock::new(|| {
+    [
+        // `%(upload_date)s - %(title)s [%(id)s]`, the id being optional
+        (
+            r"^(?<date>\d{8}|\d{4}-\d{2}-\d{2}) - (?<title>.+?)(?: \[(?<id>[0-9A-Za-z_-]{11})\])?$",
+            false,
+        ),
+        // `%(title)s [%(id)s]`, yt-dlp's default
+        (r"^(?<title>.+?) \[(?<id>[0-9A-Za-z_-]{11})\]$", false),
+        // `%(title)s-%(id)s`, youtube-dl's default
+        (r"^(?<title>.+)-(?<id>[0-9A-Za-z_-]{11})$", true),
+    ]
+    .iter()
+    .map(|(template, after_dash)| (Regex::new(template).unwrap(), *after_dash))
+    .collect()
+});
+
+/// What the stem of a media file tells about the video.
+pub struct Guess {
+    pub id: Option<String>,
+    pub title: String,
+    pub date: Option<NaiveDate>,
+}
+
+pub fn parse(stem: &str) -> Guess {
+    for (template, after_dash) in TEMPLATES.iter() {
+        let Some(captures) = template.captures(stem) else {
+            continue;
+        };
+
+        let id = captures.name("id").map(|id| id.as_str().to_string());
+        // Real ids are rarely all lowercase letters.
+        if *after_dash
+            && id
+                .as_deref()
+                .is_some_and(|id| id.chars().all(|c| c.is_ascii_lowercase()))
+        {
+            continue;
+        }
+
+        let date = captures.name("date").and_then(|date| {
+            NaiveDate::parse_from_str(date.as_str(), "%Y%m%d")
+                .or_else(|_| NaiveDate::parse_from_str(date.as_str(), "%Y-%m-%d"))
+                .ok()
+        });
+        return Guess {
+            id,
+            title: captures["title"].trim().to_string(),
+            date,
+        };
+    }
+
+    Guess {
+        id: None,
+        title: stem.to_string(),
+        date: None,
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail};
+use chrono::Datelike;
+
+use crate::{Episode, Season, naming::Normalization};
+
+/// Placeholders a layout may use, `{name}` or `{name:N}` to zero-pad numbers to N digits.
+const VARIABLES: &[&str] = &[
+    "channel",
+    "season",
+    "season_folder",
+    "episode",
+    "year",
+    "month",
+    "day",
+    "date",
+    "id",
+    "title",
+    "playlist",
+    "filename",
+];
+
+pub const DEFAULT_LAYOUT: &str = "{channel}/{season_folder}/{filename}";
+
+/// Used for channels in the `music-video` mode.
+pub const MUSIC_VIDEO_LAYOUT: &str = "{channel}/{channel} - {title} ({year})";
+
+/// Named layouts accepted in place of a template.
+const PRESETS: &[(&str, &str)] = &[
+    ("seasons", DEFAULT_LAYOUT),
+    // Episodes directly in the channel folder, for Emby "mixed content" libraries.
+    ("flat", "{channel}/{date} {filename}"),
+    ("music-video", MUSIC_VIDEO_LAYOUT),
+];
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Variable { name: String, width: usize },
+}
+
+/// Where episodes are placed in the target, e.g. `{channel}/{season_folder}/{filename}`. The last
+/// component is the file name without extension, sidecars append their own.
+#[derive(Clone)]
+pub struct Layout {
+    components: Vec<Vec<Segment>>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        DEFAULT_LAYOUT.parse().unwrap()
+    }
+}
+
+impl FromStr for Layout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template = PRESETS
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map_or(s, |(_, template)| template);
+
+        let components = template
+            .split('/')
+            .filter(|component| !component.is_empty())
+            .map(parse_component)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if components.is_empty() {
+            bail!("Layout {s:?} is empty");
+        }
+
+        Ok(Self { components })
+    }
+}
+
+fn parse_component(component: &str) -> anyhow::Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = component;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unclosed {{ in layout component {component:?}"))?;
+        let spec = &rest[start + 1..start + end];
+        let (name, width) = match spec.split_once(':') {
+            Some((name, width)) => (
+                name,
+                width
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid width in layout placeholder {{{spec}}}"))?,
+            ),
+            None => (spec, 0),
+        };
+
+        if !VARIABLES.contains(&name) {
+            bail!(
+                "Unknown layout placeholder {{{name}}}, expected one of {}",
+                VARIABLES.join(", ")
+            );
+        }
+
+        segments.push(Segment::Variable {
+            name: name.to_string(),
+            width,
+        });
+        rest = &rest[start + end + 1..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+
+    Ok(segments)
+}
+
+/// Values the placeholders of a layout are filled with for a single episode.
+pub struct LayoutContext<'a> {
+    pub channel: String,
+    pub season: &'a Season<'a>,
+    pub episode: &'a Episode<'a>,
+    pub title: String,
+    pub filename: String,
+    pub normalization: Normalization,
+}
+
+impl LayoutContext<'_> {
+    fn value(&self, name: &str, width: usize) -> String {
+        let entry = self.episode.entry;
+        let number = |n: usize| format!("{n:0>width$}");
+
+        let value = match name {
+            "channel" => self.channel.clone(),
+            "season" => number(self.season.number),
+            "season_folder" => format!("Season {}", self.season.number),
+            "episode" => number(self.episode.number),
+            "year" => number(entry.date.year() as usize),
+            "month" => format!("{:0>2}", entry.date.month()),
+            "day" => format!("{:0>2}", entry.date.day()),
+            "date" => entry.date.format("%Y-%m-%d").to_string(),
+            "id" => entry.json.id.clone(),
+            "title" => self.title.clone(),
+            "playlist" => entry.json.playlist.clone().unwrap_or_else(|| "NA".into()),
+            "filename" => self.filename.clone(),
+            _ => String::new(),
+        };
+
+        // Values must never introduce directories of their own.
+        value.replace('/', "_")
+    }
+}
+
+impl Layout {
+    fn render_component(&self, index: usize, context: &LayoutContext) -> String {
+        let component = self.components[index]
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Variable { name, width } => context.value(name, *width),
+            })
+            .collect::<String>();
+
+        context.normalization.apply(&component).into_owned()
+    }
+
+    fn render_prefix(&self, root: &Path, len: usize, context: &LayoutContext) -> PathBuf {
+        (0..len).fold(root.to_path_buf(), |path, index| {
+            path.join(self.render_component(index, context))
+        })
+    }
+
+    fn depth_of(&self, names: &[&str]) -> Option<usize> {
+        self.components[..self.components.len() - 1]
+            .iter()
+            .position(|component| {
+                component.iter().any(|segment| {
+                    matches!(segment, Segment::Variable { name, .. } if names.contains(&name.as_str()))
+                })
+            })
+            .map(|index| index + 1)
+    }
+
+    /// Directory and file stem of an episode.
+    pub fn locate(&self, root: &Path, context: &LayoutContext) -> (PathBuf, String) {
+        let last = self.components.len() - 1;
+
+        (
+            self.render_prefix(root, last, context),
+            self.render_component(last, context),
+        )
+    }
+
+    /// Directory holding the whole channel: everything up to the component naming the channel,
+    /// or the target root when the layout doesn't name channels.
+    pub fn channel_dir(&self, root: &Path, context: &LayoutContext) -> PathBuf {
+        let depth = self.depth_of(&["channel"]).unwrap_or(0);
+        self.render_prefix(root, depth, context)
+    }
+
+    /// Directory of a season: everything up to the component naming the season, `None` when the
+    /// layout has no season folders.
+    pub fn season_dir(&self, root: &Path, context: &LayoutContext) -> Option<PathBuf> {
+        self.depth_of(&["season", "season_folder"])
+            .map(|depth| self.render_prefix(root, depth, context))
+    }
+}
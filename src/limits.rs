@@ -0,0 +1,92 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::bail;
+
+use crate::plan::{LibraryPlan, Operation};
+
+/// Removed paths listed when a run is stopped for removing too much.
+const EXAMPLES: usize = 5;
+
+/// Caps on what a single run may do to a target (`--max-operations`, `--max-delete`), so a wrong
+/// SOURCE or an empty mount can't wipe out a library.
+#[derive(Clone, Copy, Default)]
+pub struct Limits {
+    pub max_operations: Option<usize>,
+    pub max_delete: Option<usize>,
+}
+
+impl Limits {
+    pub fn is_set(&self) -> bool {
+        self.max_operations.is_some() || self.max_delete.is_some()
+    }
+
+    /// Fails with a summary of what the plan of `library` would do when it goes over a limit.
+    pub fn check(&self, library: &LibraryPlan) -> anyhow::Result<()> {
+        let operations = &library.operations;
+        let removed = operations
+            .iter()
+            .filter_map(|op| match op {
+                Operation::Remove { path } => Some(path),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !self.exceeded(operations.len(), removed.len()) {
+            return Ok(());
+        }
+
+        let count =
+            |matches: fn(&Operation) -> bool| operations.iter().filter(|op| matches(op)).count();
+        let mut summary = format!(
+            "{} directories to create, {} files to place, {} files to write, {} files to remove",
+            count(|op| matches!(op, Operation::CreateDir { .. })),
+            count(|op| matches!(op, Operation::Link { .. } | Operation::HardLink { .. })),
+            count(|op| matches!(op, Operation::Write { .. } | Operation::Download { .. })),
+            removed.len()
+        );
+        for path in removed.iter().take(EXAMPLES) {
+            let _ = write!(summary, "\n  {path:?}");
+        }
+        if removed.len() > EXAMPLES {
+            let _ = write!(summary, "\n  and {} more", removed.len() - EXAMPLES);
+        }
+
+        self.fail(&library.target, operations.len(), removed.len(), &summary)
+    }
+
+    /// Fails when moving `moves` files in `target` goes over `--max-operations`.
+    pub fn check_moves(&self, target: &Path, moves: usize) -> anyhow::Result<()> {
+        if !self.exceeded(moves, 0) {
+            return Ok(());
+        }
+
+        self.fail(target, moves, 0, &format!("{moves} files to move"))
+    }
+
+    fn exceeded(&self, operations: usize, removed: usize) -> bool {
+        self.max_operations.is_some_and(|max| operations > max)
+            || self.max_delete.is_some_and(|max| removed > max)
+    }
+
+    fn fail(
+        &self,
+        target: &Path,
+        operations: usize,
+        removed: usize,
+        summary: &str,
+    ) -> anyhow::Result<()> {
+        let limit = match self.max_delete {
+            Some(max) if removed > max => {
+                format!("would remove {removed} files (--max-delete {max})")
+            }
+            _ => format!(
+                "would make {operations} changes (--max-operations {})",
+                self.max_operations.unwrap_or_default()
+            ),
+        };
+        bail!(
+            "Stopped before changing {target:?}, the run {limit}: {summary}\nCheck SOURCE and the \
+             config (an unmounted download directory looks empty), then pass --yes-really to go \
+             ahead anyway"
+        )
+    }
+}
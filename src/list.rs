@@ -0,0 +1,173 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    SeasonedStructure,
+    classify::ContentKind,
+    filename::Confidence,
+    tree::{self, Node},
+};
+
+/// What `--format` prints `list`, `stats` and the changes of a dry run as.
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// `SxxxEyyy: Title (date)` lines under every channel, line by line progress for a run
+    #[default]
+    Text,
+    Json,
+    /// One aligned row per episode (or channel, for `stats`)
+    Table,
+    /// Channels, seasons and episodes drawn as a tree, with the number of episodes in each. With
+    /// `--dry-run`, the folders and files every target would get, with the number of files placed
+    /// and removed in each folder
+    Tree,
+}
+
+#[derive(Serialize)]
+struct ChannelList {
+    channel: String,
+    seasons: Vec<SeasonList>,
+}
+
+#[derive(Serialize)]
+struct SeasonList {
+    season: usize,
+    /// What the season groups, the year or the playlist
+    key: String,
+    episodes: Vec<EpisodeList>,
+}
+
+#[derive(Serialize)]
+struct EpisodeList {
+    episode: usize,
+    id: String,
+    title: String,
+    date: String,
+    kind: ContentKind,
+    /// How reliable the metadata read from the file name is, for media without an info.json
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_filename: Option<Confidence>,
+}
+
+pub fn print(structure: &[SeasonedStructure], format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for channel in structure {
+                channel.print();
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&collect(structure))?),
+        OutputFormat::Table => print_table(structure),
+        OutputFormat::Tree => tree::print(&nodes(structure)),
+    }
+
+    Ok(())
+}
+
+fn collect(structure: &[SeasonedStructure]) -> Vec<ChannelList> {
+    structure
+        .iter()
+        .map(|channel| ChannelList {
+            channel: channel.channel_name.clone(),
+            seasons: channel
+                .seasons
+                .iter()
+                .map(|season| SeasonList {
+                    season: season.number,
+                    key: season.key.clone(),
+                    episodes: season
+                        .videos
+                        .iter()
+                        .map(|ep| EpisodeList {
+                            episode: ep.number,
+                            id: ep.entry.json.id.clone(),
+                            title: ep.entry.get_title().to_string(),
+                            date: ep.entry.date.format("%Y-%m-%d").to_string(),
+                            kind: ep.entry.kind,
+                            from_filename: ep.entry.guessed,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn nodes(structure: &[SeasonedStructure]) -> Vec<Node> {
+    let count = |n: usize, what: &str| match n {
+        1 => format!("1 {what}"),
+        n => format!("{n} {what}s"),
+    };
+
+    structure
+        .iter()
+        .map(|channel| {
+            let seasons = channel
+                .seasons
+                .iter()
+                .map(|season| {
+                    let videos = season
+                        .videos
+                        .iter()
+                        .map(|ep| {
+                            let kind = match ep.entry.kind {
+                                ContentKind::Episode => String::new(),
+                                kind => format!(" [{}]", kind.name()),
+                            };
+                            let label = format!(
+                                "E{:0>3} {} ({}){kind}",
+                                ep.number,
+                                ep.entry.get_title(),
+                                ep.entry.date.format("%Y-%m-%d")
+                            );
+                            Node::new(label, Vec::new())
+                        })
+                        .collect();
+                    let label = format!(
+                        "Season {} [{}] ({})",
+                        season.number,
+                        season.key,
+                        count(season.videos.len(), "episode")
+                    );
+                    Node::new(label, videos)
+                })
+                .collect::<Vec<_>>();
+            let total = channel.seasons.iter().map(|s| s.videos.len()).sum();
+            let label = format!(
+                "{} ({}, {})",
+                channel.channel_name,
+                count(seasons.len(), "season"),
+                count(total, "episode")
+            );
+            Node::new(label, seasons)
+        })
+        .collect()
+}
+
+fn print_table(structure: &[SeasonedStructure]) {
+    let width = structure
+        .iter()
+        .map(|c| c.channel_name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Channel".len());
+
+    println!(
+        "{:<width$}  {:>6}  {:>7}  {:<10}  Title",
+        "Channel", "Season", "Episode", "Date"
+    );
+    for channel in structure {
+        for season in &channel.seasons {
+            for ep in &season.videos {
+                println!(
+                    "{:<width$}  {:>6}  {:>7}  {:<10}  {}",
+                    channel.channel_name,
+                    season.number,
+                    ep.number,
+                    ep.entry.date.format("%Y-%m-%d"),
+                    ep.entry.get_title()
+                );
+            }
+        }
+    }
+}
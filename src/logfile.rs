@@ -0,0 +1,118 @@
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Context;
+use chrono::Local;
+
+/// The `--log-file` of the run, every line of progress is appended to it.
+static LOG: Mutex<Option<LogFile>> = Mutex::new(None);
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+/// Starts appending to `path`, moving it aside first when it's already over `max_size`.
+pub fn open(path: &Path, max_size: u64, keep: usize) -> anyhow::Result<()> {
+    let size = match path.metadata() {
+        Ok(meta) if meta.len() >= max_size => {
+            rotate(path, keep).with_context(|| format!("Can't rotate the log file {path:?}"))?;
+            0
+        }
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    };
+    let file = append(path)?;
+
+    *LOG.lock().unwrap() = Some(LogFile {
+        path: path.to_path_buf(),
+        file,
+        size,
+        max_size,
+        keep,
+    });
+
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    LOG.lock().unwrap().is_some()
+}
+
+/// Appends a timestamped line, without the colors of terminal output. A log that can't be
+/// written doesn't stop the run.
+pub fn line(text: impl Display) {
+    let mut log = LOG.lock().unwrap();
+    let Some(log) = log.as_mut() else {
+        return;
+    };
+
+    let line = format!(
+        "{} {}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        strip_colors(&text.to_string())
+    );
+    if log.size > 0 && log.size + line.len() as u64 > log.max_size {
+        let rotated = rotate(&log.path, log.keep).and_then(|_| append(&log.path));
+        if let Ok(file) = rotated {
+            log.file = file;
+            log.size = 0;
+        }
+    }
+
+    if log.file.write_all(line.as_bytes()).is_ok() {
+        log.size += line.len() as u64;
+    }
+}
+
+fn append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Renames `path` to `<path>.1`, shifting older files up and dropping the one past `keep`.
+fn rotate(path: &Path, keep: usize) -> std::io::Result<()> {
+    let rotated = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    };
+
+    if keep == 0 {
+        return std::fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        match std::fs::rename(rotated(n), rotated(n + 1)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    std::fs::rename(path, rotated(1))
+}
+
+/// Drops the escape codes `term::paint` adds.
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skips `[`, the parameters and the final `m`.
+            for c in chars.by_ref() {
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
+}
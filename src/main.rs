@@ -1,62 +1,690 @@
 use std::{
-    collections::HashMap,
-    ffi::OsString,
+    borrow::Cow,
+    cell::{Cell, OnceCell, RefCell},
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::{OsStr, OsString},
     fs::File,
-    io::ErrorKind,
-    path::{Path, PathBuf},
+    io::{ErrorKind, Read, Write},
+    net::SocketAddr,
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
 
-use chrono::{Datelike, NaiveDate, NaiveDateTime};
-use clap::Parser;
-use itertools::Itertools;
-use serde::Deserialize;
+use anyhow::{Context, bail};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use itertools::{Either, Itertools};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use walkdir::WalkDir;
 
-#[derive(thiserror::Error, Debug, Clone)]
-pub enum OrganizerError {
-    #[error("Can't parse path: {0:?}")]
-    WrongPathBuf(PathBuf),
-}
+use crate::{
+    artwork::Thumbnail,
+    backup::StateAction,
+    classify::{Classifier, ContentKind},
+    config::{ChannelConfig, ChannelMode, Config},
+    conflict::{Conflicts, Resolution},
+    daemon::{Interval, Timing},
+    dedupe::{Dedupe, DuplicateIndex},
+    error::OrganizerError,
+    events::{Event, EventFormat, Events},
+    filename::Confidence,
+    layout::{Layout, LayoutContext},
+    limits::Limits,
+    list::OutputFormat,
+    manifest::Manifest,
+    naming::{Naming, Normalization},
+    nfo::{Actor, EpisodeNfo, MusicVideoNfo},
+    plan::{Operation, Recorder},
+    playlist::ChannelPlaylist,
+    prefix::PrefixMap,
+    reconcile::TargetIndex,
+    remote::Remote,
+    retention::ByteSize,
+    route::Route,
+    schedule::Schedule,
+    sidecar::{SiblingIndex, SidecarFilter},
+    snapshot::Snapshot,
+    sort::SortOrder,
+    state::State,
+    summary::{
+        BrokenMedia, InaccessiblePath, MissingMedia, PendingPremiere, Report, Summary, TargetReport,
+    },
+    template::OutputTemplate,
+    term::{Color, ColorChoice},
+    trash::TrashPlace,
+};
 
-#[derive(Parser)]
+mod artwork;
+mod backup;
+mod broken;
+mod channels;
+mod classify;
+mod collection;
+mod config;
+mod confirm;
+mod conflict;
+mod daemon;
+mod dedupe;
+mod doctor;
+mod error;
+mod events;
+mod explain;
+mod feed;
+mod filename;
+mod layout;
+mod limits;
+mod list;
+mod logfile;
+mod manifest;
+mod media_server;
+mod metrics;
+mod migrate;
+mod naming;
+mod nfo;
+mod notify;
+mod os_path;
+mod plan;
+mod playlist;
+mod prefix;
+mod reconcile;
+mod reflink;
+mod refresh;
+mod remote;
+mod retention;
+mod route;
+mod schedule;
+mod sidecar;
+mod signals;
+mod snapshot;
+mod sort;
+mod space;
+mod state;
+mod stats;
+mod summary;
+mod template;
+mod term;
+mod throttle;
+mod trash;
+mod tree;
+mod verify;
+mod watched;
+
+#[derive(Parser, Clone)]
 struct Cli {
     source: PathBuf,
-    #[arg(long, short)]
-    target: Option<PathBuf>,
-    #[arg(long, short, action)]
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// JSON config file, defaults to `$XDG_CONFIG_HOME/ytdlp-organise/config.json`
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Where the library is built. Given more than once, the same structure is mirrored into
+    /// every target (e.g. a local library and a backup on a NAS)
+    #[arg(long, short, global = true)]
+    target: Vec<PathBuf>,
+    /// Carry the watch state of episodes `migrate` moves over to their new path on the Emby or
+    /// Jellyfin server of `"media_server"` in the config, once it has scanned them
+    #[arg(long, global = true)]
+    transfer_watched: bool,
+    /// Ask the Emby or Jellyfin server of `"media_server"` in the config to scan a target the run
+    /// changed: the library set in its `"library"`, or every library
+    #[arg(long, global = true)]
+    scan_media_server: bool,
+    /// Build the library in this directory of every target (`--library-subdir YouTube` places
+    /// channels in `TARGET/YouTube`), to share a media server library with other content
+    #[arg(long, value_name = "DIR", global = true)]
+    library_subdir: Option<PathBuf>,
+    #[arg(long, short, action, global = true)]
     dry_run: bool,
+    /// How `list` and `stats` print, and with `--dry-run` (`tree` only) what would change
+    #[arg(long, value_enum, global = true)]
+    format: Option<OutputFormat>,
+    /// Only find out whether the target is up to date: a dry run that doesn't write anything,
+    /// not even the state, events or metrics, and exits with 2 when changes are pending
+    #[arg(long, global = true)]
+    check_only: bool,
+    /// Adopt episode files renamed by hand in the target instead of linking them again
+    #[arg(long, action, global = true)]
+    reconcile: bool,
+    /// Link a representative episode thumbnail as the artwork of every season
+    #[arg(long, value_enum, global = true)]
+    season_artwork: Option<SeasonArtwork>,
+    /// Where the season artwork is placed
+    #[arg(long, value_enum, default_value_t, global = true)]
+    season_artwork_name: SeasonArtworkName,
+    /// Where artwork comes from, `download` also fetches channel fanart from thumbnail URLs
+    #[arg(long, value_enum, default_value_t, global = true)]
+    artwork: Artwork,
+    /// Additional chrono format tried first when parsing `upload_date` and `release_date`
+    #[arg(long, global = true)]
+    date_format: Option<String>,
+    /// Read newline separated info.json paths from this file (`-` for stdin) instead of walking
+    /// SOURCE
+    #[arg(long, global = true)]
+    paths_from: Option<PathBuf>,
+    /// Descend into symlinked directories while scanning SOURCE
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+    /// Don't scan deeper than this many directories below SOURCE
+    #[arg(long, global = true)]
+    max_depth: Option<usize>,
+    /// Don't cross into other filesystems (mount points) while scanning SOURCE
+    #[arg(long, global = true)]
+    same_filesystem: bool,
+    /// Also organize media files without an info.json, reading the id, title and upload date
+    /// from names like `Title [id].mkv` or `20240131 - Title.mp4` and naming the channel after
+    /// the directory. Entries missing the id or the date are flagged as guessed in `list`
+    #[arg(long, global = true)]
+    parse_filenames: bool,
+    /// List directories of SOURCE on this many threads, which helps on network mounts and
+    /// spinning disks. The result is the same as with a single thread
+    #[arg(long, value_name = "N", default_value_t = 1, global = true)]
+    scan_threads: usize,
+    /// Extensions of files sharing the stem of an info.json that are placed with the episode,
+    /// replaces the default list (media, thumbnails, subtitles, descriptions). An entry matches
+    /// the end of what follows the stem, so `vtt` covers `en.vtt`
+    #[arg(long, value_delimiter = ',', global = true)]
+    sidecar_allow: Vec<String>,
+    /// Extensions never placed with the episode, replaces the default list (part, ytdl, temp, tmp,
+    /// live_chat.json)
+    #[arg(long, value_delimiter = ',', global = true)]
+    sidecar_deny: Vec<String>,
+    /// How videos of a channel are split into seasons, the config file can override it per
+    /// channel
+    #[arg(long, value_enum, default_value_t, global = true)]
+    seasons: SeasonStrategy,
+    /// How episodes are numbered, the config file can override it per channel
+    #[arg(long, value_enum, default_value_t, global = true)]
+    numbering: Numbering,
+    /// How years without uploads affect the season numbers of `--seasons year`, the config file
+    /// can override it per channel
+    #[arg(long, value_enum, default_value_t, global = true)]
+    season_gaps: SeasonGaps,
+    /// Leave out videos whose media file is missing (only the info.json and sidecars were found)
+    /// and list them in the summary for downloading again; `--require-media false` places their
+    /// metadata anyway
+    #[arg(long, default_value_t = true, action = ArgAction::Set, global = true)]
+    require_media: bool,
+    /// Leave out videos with a media file smaller than this (`0` to place any), what a download
+    /// that failed mid-write leaves behind, and list them in the summary
+    #[arg(long, value_name = "SIZE", default_value = "100KB", global = true)]
+    min_media_size: ByteSize,
+    /// Also move the files of such videos into `_broken` in SOURCE, so yt-dlp downloads them
+    /// again
+    #[arg(long, global = true)]
+    quarantine_broken: bool,
+    /// How channels are ordered in listings and reports, and videos uploaded on the same day
+    /// within a season
+    #[arg(long, value_enum, default_value_t, global = true)]
+    sort: SortOrder,
+    /// Keep season and episode numbers handed out in previous runs (stored in the target) and
+    /// only append new ones
+    #[arg(long, global = true)]
+    stable_numbering: bool,
+    /// Skip channels whose episodes, files and numbers are the same as when they were last
+    /// organized with the same command line and config (remembered in the target's state). Files
+    /// removed from the target by hand are only placed again by a run without it
+    #[arg(long, global = true)]
+    skip_unchanged: bool,
+    /// Content kinds placed in season 0 (specials) instead of a regular season, e.g.
+    /// `livestream,movie`
+    #[arg(long, global = true, value_delimiter = ',')]
+    specials: Vec<ContentKind>,
+    /// Put every video into one show of this name, each channel becoming a season and episode
+    /// titles prefixed with the channel name
+    #[arg(long, global = true, value_name = "NAME")]
+    single_show: Option<String>,
+    /// yt-dlp output template (`-o`) used for the downloads, relative to the source directory;
+    /// media and sidecars are also looked up where it places them. Can be given multiple times,
+    /// `TYPE:TEMPLATE` is accepted as well
+    #[arg(long, global = true)]
+    output_template: Vec<OutputTemplate>,
+    /// Remove emoji and control characters (zero-width spaces, joiners, ...) from file names
+    #[arg(long, global = true)]
+    strip_emoji: bool,
+    /// Remove the channel name creators put in front of every title (`ChannelX: Title`,
+    /// `[ChannelX] Title`), ignoring case and punctuation
+    #[arg(long, global = true)]
+    strip_channel_prefix: bool,
+    /// Remove hashtags from the end of titles (`Title #shorts #viral`), the NFO keeps the
+    /// uploaded title as `originaltitle`
+    #[arg(long, global = true)]
+    strip_hashtags: bool,
+    /// Rewrite titles and channel names written in ALL CAPS in Title Case, the NFO keeps the
+    /// uploaded title as `originaltitle`
+    #[arg(long, global = true)]
+    title_case: bool,
+    /// Transliterate file names to plain ASCII, implies `--strip-emoji`
+    #[arg(long, global = true)]
+    ascii_only: bool,
+    /// Append the video id in brackets to file names (`Title [dQw4w9WgXcQ].mp4`), as expected by
+    /// the Jellyfin YouTube metadata plugin
+    #[arg(long, global = true)]
+    suffix_id: bool,
+    /// Unicode normalization of the names of files and folders written to the target, default
+    /// `nfc`
+    #[arg(long, value_enum, value_name = "FORM", global = true)]
+    unicode_normalization: Option<Normalization>,
+    /// Write a Kodi/Emby NFO with the title, air date and description next to every episode
+    #[arg(long, global = true)]
+    nfo: bool,
+    /// With `--nfo`, still link the info.json and `.description` into the target (some library
+    /// scanners get confused by them)
+    #[arg(long, requires = "nfo", global = true)]
+    link_metadata_sidecars: bool,
+    /// Name subtitles that don't carry a language (`Title.vtt`) after the `language` of their
+    /// video (`Title.en.vtt`), so media servers label the track
+    #[arg(long, global = true)]
+    tag_subtitle_language: bool,
+    /// Print at most this many lines of progress per channel, the rest is only counted in the
+    /// channel's summary line
+    #[arg(long, value_name = "N", global = true)]
+    limit_output: Option<usize>,
+    /// Also write everything a run does to this file, with the time of each line: the lines
+    /// `--limit-output` leaves out and the progress `--events` turns off included
+    #[arg(long, value_name = "PATH", global = true)]
+    log_file: Option<PathBuf>,
+    /// Move the log file to `PATH.1` and start a new one once it's this big
+    #[arg(long, value_name = "SIZE", default_value = "10MB", global = true)]
+    log_max_size: ByteSize,
+    /// Old log files kept, `PATH.1` being the newest
+    #[arg(long, value_name = "N", default_value_t = 5, global = true)]
+    log_keep: usize,
+    /// Color progress and errors: created green, skipped yellow, errors red
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+    /// Write an Emby/Jellyfin boxset for every playlist into this directory (the `collections`
+    /// folder of the server), listing the episodes placed in the target
+    #[arg(long, value_name = "DIR", global = true)]
+    collections: Option<PathBuf>,
+    /// Write M3U playlists of every channel and season into the channel folders, and the newest
+    /// 50 episodes across all channels into `Latest 50.m3u` in the target root
+    #[arg(long, global = true)]
+    playlists: bool,
+    /// Write an RSS feed of the 50 most recently organized episodes to this file, linking to
+    /// the files in the target
+    #[arg(long, value_name = "FILE", global = true)]
+    feed: Option<PathBuf>,
+    /// Send the videos matching a condition to another target root instead, e.g.
+    /// `category=Music => /media/MusicVideos`. Conditions are `category=NAME`, `channel=NAME`,
+    /// `duration>LENGTH` and `duration<LENGTH` (seconds or with an `s`, `m` or `h` unit); the first
+    /// matching route wins. Can be given multiple times
+    #[arg(long, global = true)]
+    route: Vec<Route>,
+    /// Keep the library under this size (`2TB`, `500GiB`, ...) by leaving out and removing the
+    /// oldest episodes across all channels
+    #[arg(long, global = true)]
+    max_library_size: Option<ByteSize>,
+    /// Also delete the downloaded files of episodes removed by `--max-library-size`
+    #[arg(long, requires = "max_library_size", global = true)]
+    prune_sources: bool,
+    /// Move files a run deletes (`--max-library-size`, `--on-conflict replace`) to a trash
+    /// instead of deleting them right away
+    #[arg(long, value_enum, global = true)]
+    trash: Option<TrashPlace>,
+    /// Days trashed files are kept, runs with `--trash` delete older ones for good
+    #[arg(long, value_name = "DAYS", default_value_t = 30, global = true)]
+    trash_days: u64,
+    /// Where episodes are placed in the target, default `{channel}/{season_folder}/{filename}`.
+    /// Placeholders: channel, season, season_folder, episode, year, month, day, date, id, title,
+    /// playlist and filename (the default file name); `{episode:2}` zero-pads numbers.
+    /// `flat` puts episodes directly in the channel folder, named `{date} {filename}`
+    #[arg(long, global = true)]
+    layout: Option<Layout>,
+    /// What to do when an episode file would land on a path taken by something else
+    #[arg(long, value_enum, default_value_t, global = true)]
+    on_conflict: Resolution,
+    /// Ask what to do about every conflict instead of applying `--on-conflict`
+    #[arg(long, global = true)]
+    prompt: bool,
+    /// Delete and overwrite files (`--on-conflict replace`, `--max-library-size`, `apply`,
+    /// `empty-trash`, `state import`) without asking first on the terminal
+    #[arg(long, short, global = true)]
+    yes: bool,
+    /// How files are placed in the target
+    #[arg(long, value_enum, default_value_t, global = true)]
+    link_mode: LinkMode,
+    /// Make symlinks under FROM point under TO instead, e.g. `/mnt/downloads=/data/youtube` when
+    /// the media server runs in a container that mounts SOURCE elsewhere. Can be given multiple
+    /// times, the first matching one applies
+    #[arg(long, value_name = "FROM=TO", global = true)]
+    link_prefix_map: Vec<PrefixMap>,
+    /// Start copying (`--link-mode copy` or `apply` of such a plan) even when the target's
+    /// filesystem looks too full for the files to copy
+    #[arg(long, global = true)]
+    force: bool,
+    /// With `--link-mode copy`, compare the SHA-256 of every copy with its source right after
+    /// copying and record it in the manifest, failing the run on a mismatch
+    #[arg(long, global = true)]
+    verify_copies: bool,
+    /// Stop before changing a target when the run would make more than this many changes
+    /// (directories, placed files, NFOs and artwork, removals) to it
+    #[arg(long, value_name = "N", global = true)]
+    max_operations: Option<usize>,
+    /// Stop before changing a target when the run would remove more than this many files from
+    /// it (or from SOURCE, with `--prune-sources`)
+    #[arg(long, value_name = "N", global = true)]
+    max_delete: Option<usize>,
+    /// Go ahead even when a run goes over `--max-operations` or `--max-delete`
+    #[arg(long, global = true)]
+    yes_really: bool,
+    /// Limit copying (and uploads to ssh:// targets) to this many bytes per second, e.g. `50M`,
+    /// so a large migration leaves bandwidth for streaming
+    #[arg(long, value_name = "RATE", global = true)]
+    bwlimit: Option<ByteSize>,
+    /// Retry linking, copying and creating directories this many times when the target returns a
+    /// transient error (EAGAIN, ESTALE, ... as seen on NFS and SMB), waiting twice as long after
+    /// every attempt starting with half a second
+    #[arg(long, default_value_t = 3, value_name = "N", global = true)]
+    retries: u32,
+    /// Hash media files to find byte-identical downloads across channels and playlists
+    #[arg(long, value_enum, global = true)]
+    dedupe: Option<Dedupe>,
+    /// Stream an event per action (parsed, skipped, linked, error) as it happens
+    #[arg(long, value_enum)]
+    events: Option<EventFormat>,
+    /// Append events to this file instead of writing them to stdout
+    #[arg(long, requires = "events")]
+    events_file: Option<PathBuf>,
+    /// POST a JSON summary of the run (changed channels, new episodes, errors) to this URL
+    #[arg(long)]
+    webhook_url: Option<String>,
+    /// Write node_exporter textfile collector metrics about the run to this file
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Place the videos into the target (the default)
+    Organize,
+    /// Print the channels, seasons and episodes the source would be organized into
+    List {
+        /// Only list these channels, can be given multiple times
+        #[arg(long)]
+        channel: Vec<String>,
+    },
+    /// Print every channel found in the source with its number of videos, upload dates and size
+    Channels {
+        /// Print a `channels` section for the config instead, with the settings of every channel
+        /// it doesn't configure yet left to fill in
+        #[arg(long)]
+        generate_config: bool,
+    },
+    /// Print the size of media files per channel and season
+    Stats,
+    /// Organize a single video into the target, meant to be called from yt-dlp's `--exec` with
+    /// the downloaded file or its info.json. SOURCE is still scanned to number it consistently
+    /// with the rest of its channel
+    OrganizeOne {
+        /// The info.json of the video or a file next to it sharing its stem
+        path: PathBuf,
+    },
+    /// Print how a single video is classified, where it lands in the structure and which files
+    /// would be placed where
+    Explain {
+        /// Its info.json, media file or video id
+        video: String,
+    },
+    /// Check the source, config and targets for common setup problems and print how to fix them
+    Doctor,
+    /// Download the info.json of videos missing their channel, duration, description or
+    /// thumbnails again with `yt-dlp --skip-download`, then organize the target (if one is set)
+    /// with the refreshed metadata
+    RefreshMetadata {
+        /// Refresh these videos instead, whatever metadata they have
+        ids: Vec<String>,
+        /// The yt-dlp executable
+        #[arg(long, default_value = "yt-dlp")]
+        yt_dlp: PathBuf,
+    },
+    /// Check that every channel of the source has videos in the target and every directory of
+    /// the target holds videos of the source, exits with 1 when not
+    Verify,
+    /// Move episode files placed with other naming, numbering or layout options (or by an
+    /// older version) to where they belong now, then organize the target
+    Migrate,
+    /// Delete for good what runs with `--trash` moved to the trash, from the source and the
+    /// targets
+    EmptyTrash {
+        /// Only delete what's older than `--trash-days`
+        #[arg(long)]
+        expired: bool,
+    },
+    /// Back up or restore the numbering state and manifest of the target
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// Save every video of the source and where it's organized, to compare with later using
+    /// `diff`
+    Snapshot {
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Print the videos added, removed or changed (renamed, renumbered, files added or gone)
+    /// since a snapshot, without touching the target
+    Diff {
+        #[arg(long)]
+        against: PathBuf,
+    },
+    /// Write every change organizing would make to the target into a JSON plan file, to review
+    /// or edit before running `apply`
+    Plan {
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Make the changes of a plan file written by `plan`, SOURCE isn't scanned again
+    Apply {
+        #[arg(long)]
+        plan: PathBuf,
+    },
+    /// Keep running and organize the target every interval
+    Daemon {
+        /// Time between runs, e.g. `30m` or `6h`
+        #[arg(long, default_value = "1h")]
+        interval: Interval,
+        /// Run at the times of a cron expression instead (`minute hour day month weekday` in local
+        /// time), e.g. `0 */6 * * *`
+        #[arg(long, conflicts_with = "interval")]
+        schedule: Option<Schedule>,
+        /// Serve `/status`, `/healthz` and `/last-run` over HTTP on this address, e.g.
+        /// `0.0.0.0:8080`
+        #[arg(long)]
+        listen: Option<SocketAddr>,
+    },
 }
 
-#[derive(Deserialize, Clone)]
-#[serde(tag = "_type")]
+/// What an info.json describes, told apart by its `_type`.
 pub enum InfoJson {
-    #[serde(rename = "video")]
-    Video(VideoJson),
-    #[serde(rename = "playlist")]
+    Video(Box<VideoJson>),
     Playlist,
 }
 
-#[derive(Deserialize, Clone)]
+/// Just the `_type` of an info.json, everything else is skipped without being kept.
+#[derive(Deserialize)]
+struct InfoJsonType {
+    #[serde(rename = "_type")]
+    kind: Option<String>,
+}
+
+impl InfoJson {
+    /// Parses an info.json read into memory. A serde-tagged enum would first buffer the whole
+    /// document (format lists run to hundreds of KB), so the `_type` is looked up in a pass of
+    /// its own and the video fields are then deserialized straight from the bytes.
+    pub fn from_slice(data: &[u8]) -> serde_json::Result<Self> {
+        let InfoJsonType { kind } = serde_json::from_slice(data)?;
+        InfoJson::of_kind(kind.as_deref(), || serde_json::from_slice(data))
+    }
+
+    /// Same as `from_slice` for a document already parsed, e.g. for output templates.
+    pub fn from_value(value: &Value) -> serde_json::Result<Self> {
+        let kind = value.get("_type").and_then(Value::as_str);
+        InfoJson::of_kind(kind, || VideoJson::deserialize(value))
+    }
+
+    fn of_kind(
+        kind: Option<&str>,
+        video: impl FnOnce() -> serde_json::Result<VideoJson>,
+    ) -> serde_json::Result<Self> {
+        match kind {
+            Some("video") => Ok(InfoJson::Video(Box::new(video()?))),
+            Some("playlist") => Ok(InfoJson::Playlist),
+            Some(kind) => Err(serde::de::Error::unknown_variant(
+                kind,
+                &["video", "playlist"],
+            )),
+            None => Err(serde::de::Error::missing_field("_type")),
+        }
+    }
+}
+
+#[derive(Deserialize)]
 pub struct VideoJson {
     pub id: String,
     pub title: String,
-    pub channel: String,
+    pub channel: Option<String>,
+    pub uploader: Option<String>,
+    pub uploader_id: Option<String>,
+    pub webpage_url_domain: Option<String>,
+    pub webpage_url: Option<String>,
     pub fulltitle: String,
     pub upload_date: String,
     pub timestamp: Option<i64>,
+    /// When the video went public, for premieres and streams when they aired
+    pub release_date: Option<String>,
+    pub release_timestamp: Option<i64>,
     pub playlist_webpage_url: Option<String>,
+    pub playlist: Option<String>,
+    pub playlist_id: Option<String>,
+    pub playlist_index: Option<usize>,
+    pub playlist_autonumber: Option<usize>,
+    pub view_count: Option<u64>,
+    pub description: Option<String>,
+    /// Length in seconds
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub live_status: Option<String>,
+    pub was_live: Option<bool>,
+    #[serde(default)]
+    pub thumbnails: Vec<Thumbnail>,
+    /// Spoken language as a code (`en`, `pt-BR`), when the uploader set one
+    pub language: Option<String>,
+    #[serde(default)]
+    pub creators: Vec<String>,
+    pub creator: Option<String>,
+    #[serde(default)]
+    pub artists: Vec<String>,
+    pub artist: Option<String>,
+}
+
+/// Formats seen in `upload_date` across extractors, yt-dlp's own `YYYYMMDD` first.
+const DATE_FORMATS: &[&str] = &["%Y%m%d", "%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"];
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y%m%d%H%M%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+];
+
+fn parse_date(value: &str, extra_format: Option<&str>) -> Option<NaiveDateTime> {
+    let value = value.trim();
+
+    for format in extra_format
+        .into_iter()
+        .chain(DATE_TIME_FORMATS.iter().copied())
+    {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date);
+        }
+    }
+
+    for format in extra_format.into_iter().chain(DATE_FORMATS.iter().copied()) {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Some(date.into());
+        }
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.naive_utc())
+        .ok()
 }
 
 impl VideoJson {
-    pub fn get_date(&self) -> anyhow::Result<NaiveDateTime> {
+    /// A premiere or stream that hadn't started when it was downloaded: its date is the
+    /// scheduled one and the media, if any, a placeholder.
+    pub fn is_upcoming(&self) -> bool {
+        self.live_status.as_deref() == Some("is_upcoming")
+    }
+
+    /// Premieres and streams count from when they aired, their `upload_date` can be days
+    /// earlier (when the premiere was scheduled) and would put them out of order.
+    pub fn premiered(&self) -> bool {
+        self.was_live == Some(true)
+            || matches!(
+                self.live_status.as_deref(),
+                Some("was_live" | "post_live" | "is_upcoming")
+            )
+    }
+
+    pub fn get_date(&self, extra_format: Option<&str>) -> anyhow::Result<NaiveDateTime> {
+        let released = || {
+            self.release_timestamp
+                .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+                .map(|d| d.naive_utc())
+                .or_else(|| parse_date(self.release_date.as_deref()?, extra_format))
+        };
+        if self.premiered()
+            && let Some(date) = released()
+        {
+            return Ok(date);
+        }
+
         if let Some(timestamp) = self.timestamp {
-            Ok(NaiveDateTime::from_timestamp(timestamp, 0))
+            DateTime::from_timestamp(timestamp, 0)
+                .map(|d| d.naive_utc())
+                .ok_or_else(|| anyhow::anyhow!("Timestamp out of range: {timestamp}"))
         } else {
-            let date = NaiveDate::parse_from_str(&self.upload_date, "%Y%m%d")?;
-            Ok(date.into())
+            // Some extractors only know when a video was released.
+            parse_date(&self.upload_date, extra_format)
+                .or_else(released)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Can't parse upload_date {:?} of {}",
+                        self.upload_date,
+                        self.id
+                    )
+                })
         }
     }
 
+    /// Name of the show the video belongs to. Extractors other than YouTube (Twitch, Vimeo, ...)
+    /// often have no `channel`, so fall back to the uploader and finally the site.
+    pub fn channel_name(&self) -> &str {
+        [
+            &self.channel,
+            &self.uploader,
+            &self.uploader_id,
+            &self.webpage_url_domain,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|name| name.trim())
+        .find(|name| !name.is_empty())
+        .unwrap_or("Unknown")
+    }
+
+    pub fn playlist_key(&self) -> Option<&str> {
+        self.playlist_id.as_deref().or(self.playlist.as_deref())
+    }
+
+    pub fn playlist_position(&self) -> Option<usize> {
+        self.playlist_index.or(self.playlist_autonumber)
+    }
+
     pub fn is_short(&self) -> bool {
         self.playlist_webpage_url
             .as_ref()
@@ -64,57 +692,296 @@ impl VideoJson {
     }
 }
 
-#[derive(Clone)]
-struct CatalogueEntry {
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "webm", "mov", "avi", "flv", "m4v", "m4a", "mka", "mp3", "opus", "ogg", "aac",
+    "flac", "wav",
+];
+const THUMBNAIL_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+fn is_info_json(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.as_bytes();
+        name.ends_with(b".info.json") || name.ends_with(b".info.json.gz")
+    })
+}
+
+/// Reads a whole info.json into memory, decompressing gzipped ones. Parsing from a slice is much
+/// faster than from a reader, which serde_json consumes byte by byte.
+fn read_info_json(path: &Path) -> std::io::Result<Vec<u8>> {
+    if !is_gzip(path) {
+        return std::fs::read(path);
+    }
+
+    let mut data = Vec::new();
+    flate2::read::GzDecoder::new(File::open(path)?).read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+fn is_media(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// A media file downloaded without `--write-info-json`.
+fn is_media_without_info_json(path: &Path) -> bool {
+    is_media(path)
+        && !path.with_extension("info.json").exists()
+        && !path.with_extension("info.json.gz").exists()
+}
+
+/// Old metadata compressed with gzip (`*.json.gz`), read transparently.
+fn is_gzip(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.as_bytes().ends_with(b".json.gz"))
+}
+
+pub struct CatalogueEntry {
     pub date: NaiveDateTime,
     pub json: VideoJson,
+    /// The info.json followed by its sidecars
     pub path: Vec<PathBuf>,
+    /// File stems the sidecars were looked up by, as named in the source
+    pub stems: Vec<OsString>,
+    pub kind: ContentKind,
+    /// Set for media without an info.json, whose metadata was guessed from the file name
+    pub guessed: Option<Confidence>,
 }
 
 impl CatalogueEntry {
     pub fn get_date(&self) -> NaiveDateTime {
-        self.date.clone()
+        self.date
     }
 
-    pub fn get_title(&self) -> String {
+    pub fn get_title(&self) -> &str {
         if self.json.fulltitle.len() > 1 {
-            self.json.fulltitle.clone()
+            &self.json.fulltitle
+        } else {
+            &self.json.title
+        }
+    }
+
+    /// The media files, without audio tracks downloaded next to a video.
+    pub fn get_media(&self) -> impl Iterator<Item = &PathBuf> {
+        self.path
+            .iter()
+            .filter(|p| is_media(p) && !self.is_audio_track(p))
+    }
+
+    /// An audio file of a video that has a video file too, an extra track (another language, an
+    /// audio description) rather than the media itself.
+    fn is_audio_track(&self, file: &Path) -> bool {
+        is_audio(file) && self.path.iter().any(|p| is_media(p) && !is_audio(p))
+    }
+
+    pub fn get_thumbnail(&self) -> Option<&PathBuf> {
+        self.path.iter().find(|p| {
+            p.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| THUMBNAIL_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        })
+    }
+
+    pub fn get_description(&self) -> Option<&PathBuf> {
+        self.path
+            .iter()
+            .find(|p| p.extension().is_some_and(|ext| ext == "description"))
+    }
+
+    /// The info.json, `None` when the entry was guessed from the media file name.
+    pub fn info_json(&self) -> Option<&PathBuf> {
+        self.path.first().filter(|_| self.guessed.is_none())
+    }
+
+    /// The info.json and `.description`, which are only sources of metadata.
+    pub fn is_metadata(&self, file: &Path) -> bool {
+        self.info_json().is_some_and(|info| info == file)
+            || file.extension().is_some_and(|ext| ext == "description")
+    }
+
+    /// Name of an episode file in the target: the episode stem followed by everything after the
+    /// stem of the source file (`en.vtt`), the info.json itself becomes `<stem>.json`. Subtitles
+    /// without a language (`vtt`) get `language` put in front of their extension, extra audio
+    /// tracks are renamed as described in [`audio_track_suffix`].
+    pub fn target_name(&self, stem: &str, file: &Path, language: Option<&str>) -> OsString {
+        let name = file.file_name().unwrap_or_default().as_bytes();
+        let suffix = if self.info_json().is_some_and(|info| info == file) {
+            if is_gzip(file) { "json.gz" } else { "json" }
         } else {
-            self.json.title.clone()
+            self.stems
+                .iter()
+                .find_map(|s| name.strip_prefix(s.as_bytes())?.strip_prefix(b"."))
+                .and_then(|suffix| std::str::from_utf8(suffix).ok())
+                .or_else(|| file.extension().and_then(|ext| ext.to_str()))
+                .unwrap_or_default()
+        };
+        let language = language.filter(|_| SUBTITLE_EXTENSIONS.contains(&suffix));
+        let track = Some(suffix)
+            .filter(|_| self.is_audio_track(file))
+            .and_then(|suffix| audio_track_suffix(suffix, self.json.language.as_deref()));
+        let suffix = track.as_deref().unwrap_or(suffix);
+
+        let mut target = OsString::from(stem);
+        if let Some(language) = language {
+            target.push(".");
+            target.push(language);
         }
+        if !suffix.is_empty() {
+            target.push(".");
+            target.push(suffix);
+        }
+
+        target
     }
 }
 
 impl CatalogueEntry {
-    pub fn new(path: &Path) -> anyhow::Result<Option<Self>> {
-        let json: InfoJson = serde_json::from_reader(File::open(path)?)?;
+    pub fn new(
+        path: &Path,
+        root: &Path,
+        options: &CatalogueOptions,
+        siblings: &mut SiblingIndex,
+    ) -> anyhow::Result<Option<Self>> {
+        let data = read_info_json(path).map_err(OrganizerError::io(path))?;
+        let mut locations = Vec::new();
+        let invalid = |err| OrganizerError::json(path, err);
+
+        let json = if options.output_templates.is_empty() {
+            InfoJson::from_slice(&data).map_err(invalid)?
+        } else {
+            let value: Value = serde_json::from_slice(&data).map_err(invalid)?;
+            for template in &options.output_templates {
+                locations.push(template.locate(root, &value));
+            }
+
+            InfoJson::from_value(&value).map_err(invalid)?
+        };
 
         match json {
             InfoJson::Video(video_json) => {
-                if video_json.is_short() {
+                let kind = options.classifier.classify(&video_json);
+                if kind == ContentKind::Short {
+                    options.events.emit(Event::Skipped {
+                        path,
+                        reason: "short",
+                    });
                     Ok(None)
                 } else {
+                    let date = video_json
+                        .get_date(options.date_format.as_deref())
+                        .map_err(|err| OrganizerError::ParseError {
+                            path: path.to_path_buf(),
+                            reason: err.to_string(),
+                        })?;
+                    let (path, stems) = CatalogueEntry::get_other_files(
+                        path,
+                        &locations,
+                        &options.sidecars,
+                        siblings,
+                    )?;
                     Ok(Some(CatalogueEntry {
-                        date: video_json.get_date()?,
-                        json: video_json,
-                        path: CatalogueEntry::get_other_files(path)?,
+                        date,
+                        json: *video_json,
+                        path,
+                        stems,
+                        kind,
+                        guessed: None,
                     }))
                 }
             }
-            InfoJson::Playlist => Ok(None),
+            InfoJson::Playlist => {
+                options.events.emit(Event::Skipped {
+                    path,
+                    reason: "playlist",
+                });
+                Ok(None)
+            }
         }
     }
 
-    fn get_other_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
-        let file_name = path.file_name().unwrap().to_str().unwrap();
-        let info_ext = ".info.json";
-        let ext_len = info_ext.len();
+    /// An entry for a media file downloaded without an info.json, with the id, title and date
+    /// read from its name (see `filename::parse`) and the channel named after its directory.
+    pub fn from_media(
+        path: &Path,
+        root: &Path,
+        options: &CatalogueOptions,
+        siblings: &mut SiblingIndex,
+    ) -> anyhow::Result<Option<Self>> {
+        let stem = path.file_stem().unwrap_or_default();
+        // Only the metadata is read from a UTF-8 version of the name, sidecars are looked up by
+        // the name as it is.
+        let name = stem.to_string_lossy();
+        let guess = filename::parse(&name);
+        let confidence = if guess.id.is_some() && guess.date.is_some() {
+            Confidence::High
+        } else {
+            Confidence::Low
+        };
 
-        if !file_name.ends_with(info_ext) {
-            return Ok(Vec::new());
+        let date = match guess.date {
+            Some(date) => date.into(),
+            None => {
+                let modified = std::fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .map_err(OrganizerError::io(path))?;
+                DateTime::<chrono::Utc>::from(modified).naive_utc()
+            }
+        };
+        let channel = path
+            .parent()
+            .filter(|dir| *dir != root)
+            .or(Some(root))
+            .and_then(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+
+        let json = serde_json::from_value::<VideoJson>(serde_json::json!({
+            "id": guess.id.as_deref().unwrap_or(&name),
+            "title": guess.title,
+            "fulltitle": guess.title,
+            "channel": channel,
+            "upload_date": date.format("%Y%m%d").to_string(),
+        }))?;
+        let kind = options.classifier.classify(&json);
+        if kind == ContentKind::Short {
+            options.events.emit(Event::Skipped {
+                path,
+                reason: "short",
+            });
+            return Ok(None);
         }
 
-        let file_name = &file_name[0..file_name.len() - ext_len];
+        let mut files = vec![path.to_path_buf()];
+        let dir = path.parent().unwrap_or(Path::new("."));
+        CatalogueEntry::find_by_stem(dir, stem, &options.sidecars, siblings, &mut files)?;
+        files[1..].sort();
+
+        Ok(Some(CatalogueEntry {
+            date,
+            json,
+            path: files,
+            stems: vec![stem.to_os_string()],
+            kind,
+            guessed: Some(confidence),
+        }))
+    }
+
+    /// Collects sidecars sharing the stem of the info.json, plus sidecars with the given stems in
+    /// the given directories. Returns the files and the stems used.
+    fn get_other_files(
+        path: &Path,
+        locations: &[(PathBuf, String)],
+        filter: &SidecarFilter,
+        siblings: &mut SiblingIndex,
+    ) -> anyhow::Result<(Vec<PathBuf>, Vec<OsString>)> {
+        let file_name = path.file_name().unwrap_or_default().as_bytes();
+        let Some(file_name) = file_name
+            .strip_suffix(b".info.json")
+            .or_else(|| file_name.strip_suffix(b".info.json.gz"))
+        else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let file_name = OsStr::from_bytes(file_name);
 
         let dirname = match path.parent() {
             Some(dirname) => dirname,
@@ -122,74 +989,582 @@ impl CatalogueEntry {
         };
 
         let mut r = Vec::new();
+        let mut stems = vec![file_name.to_os_string()];
         r.push(PathBuf::from(path));
-        for e in std::fs::read_dir(dirname)?.flatten() {
-            if e.file_type()?.is_file() {
-                let other_name = match e.path().file_stem().unwrap().to_os_string().into_string() {
-                    Ok(name) => name,
-                    Err(_) => continue,
-                };
+        CatalogueEntry::find_by_stem(dirname, file_name, filter, siblings, &mut r)?;
 
-                if other_name == file_name {
-                    r.push(e.path().clone());
-                }
+        for (dir, stem) in locations {
+            if dir.is_dir() {
+                CatalogueEntry::find_by_stem(dir, stem.as_ref(), filter, siblings, &mut r)?;
+                stems.push(stem.into());
+            }
+        }
+
+        // Directory listing order depends on the filesystem.
+        r[1..].sort();
+
+        // Longest first so a stem that is a prefix of another one doesn't win.
+        stems.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        stems.dedup();
+
+        Ok((r, stems))
+    }
+
+    fn find_by_stem(
+        dirname: &Path,
+        stem: &OsStr,
+        filter: &SidecarFilter,
+        siblings: &mut SiblingIndex,
+        r: &mut Vec<PathBuf>,
+    ) -> anyhow::Result<()> {
+        for other_name in siblings.starting_with(dirname, stem)? {
+            // Sidecar extensions are ASCII, a suffix that isn't UTF-8 is the name of another file.
+            let Some(suffix) = other_name
+                .as_bytes()
+                .strip_prefix(stem.as_bytes())
+                .and_then(|rest| rest.strip_prefix(b"."))
+                .and_then(|rest| std::str::from_utf8(rest).ok())
+            else {
+                continue;
+            };
+
+            let path = dirname.join(other_name);
+            if is_sidecar_suffix(suffix) && filter.accepts(suffix) && !r.contains(&path) {
+                r.push(path);
             }
         }
 
-        Ok(r)
+        Ok(())
+    }
+}
+
+/// Subtitle formats Emby and Jellyfin pick up next to a video.
+const SUBTITLE_EXTENSIONS: &[&str] = &["srt", "vtt", "ass", "ssa", "sub"];
+
+const AUDIO_EXTENSIONS: &[&str] = &["m4a", "mka", "mp3", "opus", "ogg", "aac", "flac", "wav"];
+
+/// Words yt-dlp and uploaders mark audio description tracks with (`en-desc`, `audio_desc`).
+const AUDIO_DESCRIPTION_MARKERS: &[&str] = &["desc", "descriptive", "description", "ad"];
+
+fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Suffix of an audio track downloaded next to the video, following Emby's naming of external
+/// audio: `en-US-desc.m4a` becomes `en.audio_desc.m4a` and `en.m4a` stays. Descriptions without a
+/// language in their name take the `language` of the video. `None` for suffixes that aren't an
+/// extra track or whose language is unknown (`f140.m4a`), they keep their name.
+fn audio_track_suffix(suffix: &str, language: Option<&str>) -> Option<String> {
+    let (name, extension) = suffix.rsplit_once('.')?;
+    if !AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+        return None;
     }
+
+    let words = name
+        .split(['.', '-', '_'])
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>();
+    let description = words
+        .iter()
+        .any(|word| AUDIO_DESCRIPTION_MARKERS.contains(&word.as_str()));
+    let language = words
+        .first()
+        .filter(|word| {
+            (2..=3).contains(&word.len())
+                && word.chars().all(|c| c.is_ascii_alphabetic())
+                && !AUDIO_DESCRIPTION_MARKERS.contains(&word.as_str())
+        })
+        .cloned()
+        .or_else(|| {
+            language.filter(|_| description).map(|language| {
+                language
+                    .split('-')
+                    .next()
+                    .unwrap_or(language)
+                    .to_lowercase()
+            })
+        })?;
+
+    Some(match description {
+        true => format!("{language}.audio_desc.{extension}"),
+        false => format!("{language}.{extension}"),
+    })
+}
+
+/// Whether what follows the stem is an extension with at most two of the qualifiers yt-dlp puts
+/// before it (`en.vtt`, `f140.m4a`, `live_chat.json`) rather than the rest of the title of another
+/// video (`Ep. 1.5.mp4` or `Ep. 1.5 Finale.mp4` next to `Ep. 1.mp4`).
+fn is_sidecar_suffix(suffix: &str) -> bool {
+    if suffix == "info.json" || suffix == "info.json.gz" {
+        return false;
+    }
+
+    let suffix = suffix.strip_suffix(".gz").unwrap_or(suffix);
+    let mut parts = suffix.rsplit('.');
+    let extension = parts.next().unwrap_or_default();
+    let qualifiers = parts.collect::<Vec<_>>();
+
+    !extension.is_empty()
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        && !extension.chars().all(|c| c.is_ascii_digit())
+        && qualifiers.len() <= 2
+        && qualifiers.iter().all(|part| is_sidecar_qualifier(part))
+}
+
+/// Qualifiers in the names of sidecars: a language (`en`, `pt-BR`, `en-US-desc`), a format id
+/// (`f140`, `f251-drc`), the name of a JSON sidecar (`live_chat`) or a subtitle flag (`forced`).
+fn is_sidecar_qualifier(part: &str) -> bool {
+    const NAMES: &[&str] = &["live_chat", "rechat", "forced", "default"];
+
+    let mut segments = part.split('-');
+    let first = segments.next().unwrap_or_default();
+    let rest_ok = segments.all(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    let language = (2..=3).contains(&first.len()) && first.chars().all(|c| c.is_ascii_alphabetic());
+    let format = first
+        .strip_prefix('f')
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()));
+
+    NAMES.contains(&part) || ((language || format) && rest_ok)
+}
+
+#[derive(Clone, Default)]
+pub struct CatalogueOptions {
+    pub verbose: bool,
+    pub events: Events,
+    pub date_format: Option<String>,
+    pub output_templates: Vec<OutputTemplate>,
+    pub sidecars: SidecarFilter,
+    pub follow_symlinks: bool,
+    pub max_depth: Option<usize>,
+    pub same_filesystem: bool,
+    /// Threads listing directories while scanning, a single one walks sequentially
+    pub scan_threads: usize,
+    /// Also organize media files without an info.json, see `CatalogueEntry::from_media`
+    pub parse_filenames: bool,
+    pub classifier: Classifier,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeasonStrategy {
+    /// One season per upload year
+    #[default]
+    Year,
+    /// One season per playlist, numbered by `playlist_index`; videos outside of any playlist end
+    /// up in season 0
+    Playlist,
 }
 
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Numbering {
+    /// Episode numbers start over in every season
+    #[default]
+    PerSeason,
+    /// Episodes are numbered continuously across all seasons of a channel (season 0 keeps its
+    /// own numbers)
+    Absolute,
+}
+
+/// How years without uploads show up in the season numbers of `--seasons year`.
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SeasonGaps {
+    /// Seasons are numbered one after another, a year without uploads takes no number
+    #[default]
+    Contiguous,
+    /// Season 1 is the first year and a year without uploads leaves its number unused
+    Skip,
+    /// The year is the season number (`Season 2021`)
+    Year,
+}
+
+/// How the videos of a channel are split into seasons and numbered.
+#[derive(Clone, Copy, Default)]
+pub struct SeasonOptions {
+    pub strategy: SeasonStrategy,
+    pub numbering: Numbering,
+    pub gaps: SeasonGaps,
+}
+
+/// A directory containing one of these files is skipped together with everything below it.
+const IGNORE_MARKERS: &[&str] = &[".organise-ignore", ".nomedia"];
+
 pub struct VideoCatalogue {
     raw: Vec<CatalogueEntry>,
+    /// What the walk of the source couldn't read
+    inaccessible: Vec<InaccessiblePath>,
+}
+
+fn is_ignored(dir: &Path) -> bool {
+    IGNORE_MARKERS
+        .iter()
+        .any(|marker| dir.join(marker).exists())
+}
+
+fn report_ignored(dir: &Path, options: &CatalogueOptions) {
+    if options.verbose {
+        term::say(Color::Yellow, "Ignoring", dir.display());
+    }
+    options.events.emit(Event::Skipped {
+        path: dir,
+        reason: "ignored",
+    });
+}
+
+fn is_permission_denied(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|err| err.downcast_ref::<std::io::Error>())
+        .any(|err| err.kind() == ErrorKind::PermissionDenied)
+}
+
+/// Reports a directory or file of the source that couldn't be read. The scan goes on without it.
+fn report_inaccessible(
+    path: Option<&Path>,
+    error: String,
+    options: &CatalogueOptions,
+    inaccessible: &mut Vec<InaccessiblePath>,
+) {
+    let path = path.unwrap_or(Path::new("")).to_path_buf();
+    if options.verbose {
+        term::say(
+            Color::Yellow,
+            "Unreadable",
+            format_args!("{path:?}: {error}"),
+        );
+    }
+    options.events.emit(Event::Skipped {
+        path: &path,
+        reason: "inaccessible",
+    });
+    inaccessible.push(InaccessiblePath { path, error });
 }
 
 impl VideoCatalogue {
-    pub fn build(source: PathBuf) -> anyhow::Result<Self> {
-        let mut cat = Vec::new();
+    pub fn build(source: PathBuf, options: &CatalogueOptions) -> anyhow::Result<Self> {
+        let mut inaccessible = Vec::new();
+        if options.scan_threads > 1 {
+            let paths = VideoCatalogue::walk_parallel(&source, options, &mut inaccessible)?;
+            let cat = VideoCatalogue::from_paths(&source, paths, options)?;
+            return Ok(cat.with_inaccessible(inaccessible));
+        }
+
+        let mut walk = WalkDir::new(&source)
+            .sort_by_file_name()
+            .follow_links(options.follow_symlinks)
+            .same_file_system(options.same_filesystem);
+        if let Some(depth) = options.max_depth {
+            walk = walk.max_depth(depth);
+        }
 
-        let iter = WalkDir::new(source)
+        let paths = walk
             .into_iter()
-            .filter_map(|e| if let Ok(entry) = e { Some(entry) } else { None })
-            .filter(|e| {
-                if let Some(ext) = e.path().extension() {
-                    ext == "json"
-                } else {
-                    false
+            .filter_entry(|e| {
+                let ignored = e.file_type().is_dir() && is_ignored(e.path());
+                if ignored {
+                    report_ignored(e.path(), options);
+                }
+
+                !ignored
+            })
+            .filter_map(|e| match e {
+                Ok(e) => Some(e.into_path()),
+                Err(err) => {
+                    let error = err.io_error().map_or(err.to_string(), |io| io.to_string());
+                    report_inaccessible(err.path(), error, options, &mut inaccessible);
+                    None
                 }
             });
 
-        for e in iter {
-            println!("Parsing {:?}", e.file_name());
-            let entry = CatalogueEntry::new(e.path())?;
+        let cat = VideoCatalogue::from_paths(&source, paths, options)?;
+        Ok(cat.with_inaccessible(inaccessible))
+    }
+
+    /// Adds what the walk couldn't read to the info.json files that couldn't be read.
+    fn with_inaccessible(mut self, inaccessible: Vec<InaccessiblePath>) -> Self {
+        self.inaccessible.extend(inaccessible);
+        self.inaccessible.sort_by(|a, b| a.path.cmp(&b.path));
+        self
+    }
+
+    /// Directories and files of the source the scan couldn't read, ordered by path.
+    pub fn inaccessible(&self) -> &[InaccessiblePath] {
+        &self.inaccessible
+    }
+
+    /// Lists the directories of `source` on a pool of `--scan-threads` threads. Entries still come
+    /// out in the same depth first, name sorted order as the single-threaded walk.
+    fn walk_parallel<'a>(
+        source: &Path,
+        options: &'a CatalogueOptions,
+        inaccessible: &'a mut Vec<InaccessiblePath>,
+    ) -> anyhow::Result<impl Iterator<Item = PathBuf> + 'a> {
+        if is_ignored(source) {
+            report_ignored(source, options);
+            return Ok(Either::Left(std::iter::empty()));
+        }
+
+        #[cfg(unix)]
+        let device = {
+            use std::os::unix::fs::MetadataExt;
+
+            let device = std::fs::metadata(source)
+                .map_err(OrganizerError::io(source))?
+                .dev();
+            move |path: &Path| std::fs::metadata(path).map(|meta| meta.dev()).ok() == Some(device)
+        };
+        #[cfg(not(unix))]
+        let device = |_: &Path| true;
+        let same_filesystem = options.same_filesystem;
+
+        // The entry state marks directories skipped for an ignore marker, so they can be reported
+        // in order on this thread.
+        let mut walk = jwalk::WalkDirGeneric::<((), bool)>::new(source)
+            .sort(true)
+            .skip_hidden(false)
+            .follow_links(options.follow_symlinks)
+            .parallelism(jwalk::Parallelism::RayonNewPool(options.scan_threads))
+            .process_read_dir(move |_, _, _, children| {
+                for child in children.iter_mut().flatten() {
+                    let Some(dir) = child.read_children_path.clone() else {
+                        continue;
+                    };
+                    if is_ignored(&dir) {
+                        child.client_state = true;
+                        child.read_children_path = None;
+                    } else if same_filesystem && !device(&dir) {
+                        child.read_children_path = None;
+                    }
+                }
+            });
+        if let Some(depth) = options.max_depth {
+            walk = walk.max_depth(depth);
+        }
+
+        let paths = walk.into_iter().filter_map(|entry| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    let error = err.io_error().map_or(err.to_string(), |io| io.to_string());
+                    report_inaccessible(err.path(), error, options, inaccessible);
+                    return None;
+                }
+            };
+            // jwalk keeps a directory it couldn't list and stores the error in it.
+            if let Some(err) = &entry.read_children_error {
+                let error = err.io_error().map_or(err.to_string(), |io| io.to_string());
+                report_inaccessible(Some(&entry.path()), error, options, inaccessible);
+            }
+            if entry.client_state {
+                report_ignored(&entry.path(), options);
+                None
+            } else {
+                Some(entry.path())
+            }
+        });
+
+        Ok(Either::Right(paths))
+    }
+
+    /// Builds the catalogue from an explicit list of info.json files instead of walking `source`.
+    pub fn from_paths(
+        source: &Path,
+        paths: impl IntoIterator<Item = PathBuf>,
+        options: &CatalogueOptions,
+    ) -> anyhow::Result<Self> {
+        let mut cat = Vec::new();
+        let mut inaccessible = Vec::new();
+        let mut siblings = SiblingIndex::default();
+
+        // Only `*.info.json`: other JSON sidecars (`live_chat.json`, ...) aren't video metadata.
+        let iter = paths.into_iter().filter(|path| {
+            is_info_json(path) || (options.parse_filenames && is_media_without_info_json(path))
+        });
+
+        for path in iter {
+            if signals::interrupted() {
+                bail!("Interrupted while scanning the source");
+            }
+            if options.verbose {
+                let name = path.file_name().unwrap_or_default();
+                term::say(Color::Dim, "Parsing", Path::new(name).display());
+            }
+            let entry = if is_info_json(&path) {
+                CatalogueEntry::new(&path, source, options, &mut siblings)
+            } else {
+                CatalogueEntry::from_media(&path, source, options, &mut siblings)
+            };
+            // Like an unreadable directory, an unreadable file only leaves its video out.
+            let entry = match entry {
+                Err(err) if is_permission_denied(&err) => {
+                    let error = err.root_cause().to_string();
+                    report_inaccessible(Some(&path), error, options, &mut inaccessible);
+                    continue;
+                }
+                entry => entry?,
+            };
+            if let Some(video) = &entry
+                && video.guessed == Some(Confidence::Low)
+                && options.verbose
+            {
+                term::print(format_args!(
+                    "No id or upload date in the name of {:?}, guessed {} from {}",
+                    path.file_name().unwrap_or_default(),
+                    video.json.id,
+                    video.date.date()
+                ));
+            }
             if let Some(video) = entry {
+                options.events.emit(Event::Parsed {
+                    path: &path,
+                    id: &video.json.id,
+                    channel: video.json.channel_name(),
+                    kind: video.kind,
+                });
                 cat.push(video);
             }
         }
 
-        Ok(Self { raw: cat })
+        Ok(Self {
+            raw: cat,
+            inaccessible,
+        })
     }
 
-    fn by_channel(&self) -> HashMap<String, Vec<&CatalogueEntry>> {
-        self.raw
-            .iter()
-            .into_group_map_by(|e| e.json.channel.clone())
+    pub fn entries(&self) -> impl Iterator<Item = &CatalogueEntry> {
+        self.raw.iter()
+    }
+
+    /// Entries grouped by channel, ordered by channel name so runs are reproducible.
+    fn by_channel(&self) -> BTreeMap<&str, Vec<&CatalogueEntry>> {
+        let mut channels = BTreeMap::<_, Vec<_>>::new();
+        for entry in &self.raw {
+            channels
+                .entry(entry.json.channel_name())
+                .or_default()
+                .push(entry);
+        }
+
+        channels
     }
 
-    pub fn build_seasons<'a>(&'a self) -> Vec<SeasonedStructure<'a>> {
+    /// Splits every channel into seasons, `channels` may override the strategy per channel. Only
+    /// the videos matching `keep` are included.
+    pub fn build_seasons<'a>(
+        &'a self,
+        seasons: SeasonOptions,
+        sort: SortOrder,
+        channels: &HashMap<String, ChannelConfig>,
+        specials: &[ContentKind],
+        keep: impl Fn(&CatalogueEntry) -> bool,
+    ) -> Vec<SeasonedStructure<'a>> {
         let mut r = Vec::new();
         let chans = self.by_channel();
-        for (c, vids) in chans {
-            r.push(VideoCatalogue::build_channel(&c, vids));
+        for (c, mut vids) in chans {
+            vids.retain(|entry| keep(entry));
+            if vids.is_empty() {
+                continue;
+            }
+
+            r.push(VideoCatalogue::build_channel(
+                c,
+                vids,
+                channels.get(c),
+                seasons,
+                sort,
+                specials,
+            ));
         }
 
+        r.sort_by(|a, b| sort.channels(&a.channel_name, &b.channel_name));
         r
     }
 
-    fn build_channel<'a>(name: &str, mut vids: Vec<&'a CatalogueEntry>) -> SeasonedStructure<'a> {
+    fn build_channel<'a>(
+        name: &str,
+        mut vids: Vec<&'a CatalogueEntry>,
+        config: Option<&ChannelConfig>,
+        options: SeasonOptions,
+        sort: SortOrder,
+        specials: &[ContentKind],
+    ) -> SeasonedStructure<'a> {
+        vids.sort_by(|a, b| sort.videos(a, b));
+
+        let strategy = config.and_then(|c| c.seasons).unwrap_or(options.strategy);
+        let numbering = config
+            .and_then(|c| c.numbering)
+            .unwrap_or(options.numbering);
+        let gaps = config.and_then(|c| c.season_gaps).unwrap_or(options.gaps);
+
+        let (special, vids): (Vec<_>, Vec<_>) =
+            vids.into_iter().partition(|v| specials.contains(&v.kind));
+
+        let mut seasons = match strategy {
+            SeasonStrategy::Year => VideoCatalogue::seasons_by_year(vids, gaps),
+            SeasonStrategy::Playlist => VideoCatalogue::seasons_by_playlist(vids),
+        };
+
+        if !special.is_empty() {
+            // Specials join the videos outside of any playlist in season 0, in date order.
+            let mut season_zero = match seasons.first() {
+                Some(season) if season.number == 0 => seasons.remove(0).videos,
+                _ => Vec::new(),
+            }
+            .into_iter()
+            .map(|ep| ep.entry)
+            .chain(special)
+            .collect::<Vec<_>>();
+            season_zero.sort_by(|a, b| sort.videos(a, b));
+
+            seasons.insert(
+                0,
+                Season {
+                    number: 0,
+                    key: String::new(),
+                    videos: season_zero
+                        .into_iter()
+                        .enumerate()
+                        .map(|(ep, entry)| Episode {
+                            number: ep + 1,
+                            entry,
+                        })
+                        .collect(),
+                },
+            );
+        }
+
+        if numbering == Numbering::Absolute {
+            let mut number = 0;
+            for season in seasons.iter_mut().filter(|s| s.number != 0) {
+                for ep in &mut season.videos {
+                    number += 1;
+                    ep.number = number;
+                }
+            }
+        }
+
+        if let Some(config) = config {
+            for season in seasons.iter_mut().filter(|s| s.number != 0) {
+                season.number += config.season_offset;
+                for ep in &mut season.videos {
+                    ep.number += config.episode_offset;
+                }
+            }
+        }
+
+        SeasonedStructure {
+            channel_name: name.to_string(),
+            seasons,
+        }
+    }
+
+    fn seasons_by_year(vids: Vec<&CatalogueEntry>, gaps: SeasonGaps) -> Vec<Season<'_>> {
         let mut seasons = Vec::new();
+        let first_year = vids.first().map_or(0, |v| v.date.year());
 
-        vids.sort_by_key(|a| a.date);
         for (index, (year, vids)) in vids
             .iter()
             .chunk_by(|v| v.date.year())
@@ -197,47 +1572,203 @@ impl VideoCatalogue {
             .enumerate()
         {
             let mut videos_in_season = Vec::new();
-            for v in vids {
-                videos_in_season.push(*v);
+            for (ep, v) in vids.enumerate() {
+                videos_in_season.push(Episode {
+                    number: ep + 1,
+                    entry: v,
+                });
             }
 
             seasons.push(Season {
-                number: index + 1,
+                number: match gaps {
+                    SeasonGaps::Contiguous => index + 1,
+                    SeasonGaps::Skip => (year - first_year) as usize + 1,
+                    SeasonGaps::Year => year as usize,
+                },
+                key: year.to_string(),
                 videos: videos_in_season,
             });
         }
 
-        SeasonedStructure {
-            channel_name: name.to_string(),
-            seasons,
+        seasons
+    }
+
+    /// Expects `vids` sorted by date, playlists are numbered in order of their oldest video.
+    fn seasons_by_playlist(vids: Vec<&CatalogueEntry>) -> Vec<Season<'_>> {
+        let (listed, loose): (Vec<_>, Vec<_>) = vids
+            .into_iter()
+            .partition(|v| v.json.playlist_key().is_some());
+
+        let mut playlists = listed
+            .into_iter()
+            .into_group_map_by(|v| v.json.playlist_key().unwrap_or_default())
+            .into_iter()
+            .collect::<Vec<_>>();
+        playlists.sort_by(|(a_key, a), (b_key, b)| {
+            a[0].date.cmp(&b[0].date).then_with(|| a_key.cmp(b_key))
+        });
+
+        let mut seasons = Vec::new();
+
+        if !loose.is_empty() {
+            seasons.push(Season {
+                number: 0,
+                key: String::new(),
+                videos: loose
+                    .into_iter()
+                    .enumerate()
+                    .map(|(ep, entry)| Episode {
+                        number: ep + 1,
+                        entry,
+                    })
+                    .collect(),
+            });
+        }
+
+        for (index, (key, mut vids)) in playlists.into_iter().enumerate() {
+            vids.sort_by_key(|v| {
+                (
+                    v.json.playlist_position().is_none(),
+                    v.json.playlist_position(),
+                )
+            });
+
+            let mut last = 0;
+            let mut videos_in_season = Vec::new();
+            for entry in vids {
+                let number = entry.json.playlist_position().unwrap_or(last + 1);
+                last = last.max(number);
+
+                videos_in_season.push(Episode { number, entry });
+            }
+
+            seasons.push(Season {
+                number: index + 1,
+                key: key.to_string(),
+                videos: videos_in_season,
+            });
         }
+
+        seasons
     }
 }
 
+#[derive(Clone)]
+pub struct Episode<'a> {
+    pub number: usize,
+    pub entry: &'a CatalogueEntry,
+}
+
+#[derive(Clone)]
 pub struct Season<'a> {
     pub number: usize,
-    pub videos: Vec<&'a CatalogueEntry>,
+    /// What the season groups, the year or the playlist
+    pub key: String,
+    pub videos: Vec<Episode<'a>>,
 }
 
 impl<'a> Season<'a> {
     fn print(&self) {
-        for (ep, v) in self.videos.iter().enumerate() {
+        for ep in self.videos.iter() {
+            let kind = match ep.entry.kind {
+                ContentKind::Episode => String::new(),
+                kind => format!(" [{}]", kind.name()),
+            };
+            let guessed = match ep.entry.guessed {
+                None => "",
+                Some(Confidence::High) => " [from file name]",
+                Some(Confidence::Low) => " [guessed from file name]",
+            };
             println!(
-                " S{:0>3}E{ep:0>3}: {} ({})",
+                " S{:0>3}E{:0>3}: {} ({}){kind}{guessed}",
                 self.number,
-                v.get_title(),
-                v.get_date()
+                ep.number,
+                ep.entry.get_title(),
+                ep.entry.get_date()
             );
         }
     }
 }
 
+#[derive(Clone)]
 pub struct SeasonedStructure<'a> {
     pub channel_name: String,
     pub seasons: Vec<Season<'a>>,
 }
 
 impl<'a> SeasonedStructure<'a> {
+    /// Keeps only the episodes matching `keep`, dropping seasons left empty. Returns whether
+    /// anything is left of the channel.
+    fn retain_videos(&mut self, keep: impl Fn(&CatalogueEntry) -> bool) -> bool {
+        for season in &mut self.seasons {
+            season.videos.retain(|ep| keep(ep.entry));
+        }
+        self.seasons.retain(|season| !season.videos.is_empty());
+
+        !self.seasons.is_empty()
+    }
+
+    /// Merges all channels into a single show named `name`, every channel becomes one season
+    /// keeping the order of its episodes. Channels are the season their config gives them
+    /// (`show_season`), the others take the numbers after the highest one by name.
+    fn single_show(
+        name: &str,
+        channels: Vec<SeasonedStructure<'a>>,
+        configs: &HashMap<String, ChannelConfig>,
+    ) -> anyhow::Result<Self> {
+        let mut pinned = HashMap::new();
+        for (channel, config) in configs.iter().sorted_by_key(|(channel, _)| *channel) {
+            let Some(number) = config.show_season else {
+                continue;
+            };
+            if number == 0 {
+                bail!("\"show_season\" of {channel:?} must be 1 or more");
+            }
+            if let Some(other) = pinned.insert(number, channel.as_str()) {
+                bail!("{other:?} and {channel:?} are both \"show_season\" {number}");
+            }
+        }
+
+        let mut next = pinned.keys().max().map_or(1, |last| last + 1);
+        let seasons = channels
+            .into_iter()
+            .map(|channel| {
+                let number = match configs
+                    .get(&channel.channel_name)
+                    .and_then(|config| config.show_season)
+                {
+                    Some(number) => number,
+                    None => {
+                        let number = next;
+                        next += 1;
+                        number
+                    }
+                };
+                (number, channel)
+            })
+            .sorted_by_key(|(number, _)| *number)
+            .map(|(number, channel)| Season {
+                number,
+                key: channel.channel_name,
+                videos: channel
+                    .seasons
+                    .into_iter()
+                    .flat_map(|season| season.videos)
+                    .enumerate()
+                    .map(|(n, ep)| Episode {
+                        number: n + 1,
+                        entry: ep.entry,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            channel_name: name.to_string(),
+            seasons,
+        })
+    }
+
     fn print(&self) {
         println!("Channel: {}", self.channel_name);
         for s in &self.seasons {
@@ -246,134 +1777,2101 @@ impl<'a> SeasonedStructure<'a> {
     }
 }
 
-pub struct DirectoryBuilder<'a> {
-    channel: SeasonedStructure<'a>,
-    base: PathBuf,
-    dry_run: bool,
-    verbose: bool,
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SeasonArtwork {
+    /// Thumbnail of the first episode in the season
+    First,
+    /// Thumbnail of the episode with the highest view count
+    MostViewed,
 }
 
-impl<'a> DirectoryBuilder<'a> {
-    pub fn new(base_path: &PathBuf, channel: SeasonedStructure<'a>, dry_run: bool) -> Self {
-        let mut base = base_path.clone();
-        base.push(channel.channel_name.clone());
-        Self {
-            channel,
-            base,
-            dry_run,
-            verbose: true,
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum SeasonArtworkName {
+    /// `Season N/folder.jpg`
+    #[default]
+    Folder,
+    /// `seasonNN-poster.jpg` in the channel directory
+    Poster,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum Artwork {
+    /// Only use images downloaded next to the videos
+    #[default]
+    Local,
+    /// Additionally download the largest thumbnail as channel fanart, cached between runs
+    Download,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMode {
+    /// Symbolic links pointing at the source files
+    #[default]
+    Symlink,
+    /// Copy-on-write clones (btrfs, XFS, APFS), falls back to copying where not supported
+    Reflink,
+    /// Independent copies
+    Copy,
+}
+
+#[derive(Clone, Default)]
+pub struct BuilderOptions {
+    pub dry_run: bool,
+    pub verbose: bool,
+    pub events: Events,
+    pub reconcile: bool,
+    pub season_artwork: Option<SeasonArtwork>,
+    pub season_artwork_name: SeasonArtworkName,
+    pub artwork: Artwork,
+    pub link_mode: LinkMode,
+    pub naming: Naming,
+    pub conflicts: Conflicts,
+    pub nfo: bool,
+    pub link_metadata_sidecars: bool,
+    /// Hash every copy and its source, see `DirectoryBuilder::verify_copy`
+    pub verify_copies: bool,
+    /// Changes a run may make before stopping, none with `--yes-really`
+    pub limits: Limits,
+    pub link_prefix_map: Vec<PrefixMap>,
+    pub layout: Layout,
+    /// Write music video NFOs instead of episode ones
+    pub music_video: bool,
+    /// Name subtitles without a language after the language of their video
+    pub tag_subtitle_language: bool,
+    /// Verbose lines printed per channel before the rest is only counted
+    pub limit_output: Option<usize>,
+    /// Attempts repeated after a transient filesystem error
+    pub retries: u32,
+    /// Collects the changes of a dry run for `plan`
+    pub plan: Recorder,
+    /// Tie breaker of videos uploaded at the same time in collections and playlists
+    pub sort: SortOrder,
+}
+
+pub struct DirectoryBuilder<'a> {
+    channel: SeasonedStructure<'a>,
+    root: PathBuf,
+    base: PathBuf,
+    dry_run: bool,
+    verbose: bool,
+    options: BuilderOptions,
+    reflink_warned: Cell<bool>,
+    duplicates: Option<&'a DuplicateIndex>,
+    /// Episode files placed during this run and the video they belong to
+    targets: RefCell<HashMap<PathBuf, String>>,
+    /// Directories already created (or found) during this run
+    created: RefCell<HashSet<PathBuf>>,
+    /// Hashes of the copies checked with `--verify-copies`, until recorded in the manifest
+    verified: RefCell<HashMap<PathBuf, String>>,
+    /// Verbose lines produced for the channel, printed or not
+    printed: Cell<usize>,
+    /// Avatar of the channel for the NFOs, looked up with the first one
+    avatar: OnceCell<Option<String>>,
+    /// `S01E02` and title of the episode being placed, the first columns of verbose lines
+    current: RefCell<Option<(String, String)>>,
+    /// Width of the title column, the longest title of the channel up to `MAX_TITLE_WIDTH`
+    title_width: usize,
+    summary: RefCell<Summary>,
+}
+
+impl<'a> DirectoryBuilder<'a> {
+    pub fn new(base_path: &Path, channel: SeasonedStructure<'a>, options: &BuilderOptions) -> Self {
+        // The channel directory comes from the layout, rendered for the first episode.
+        let base = channel
+            .seasons
+            .iter()
+            .find_map(|season| Some((season, season.videos.first()?)))
+            .map(|(season, episode)| {
+                let context = LayoutContext {
+                    channel: options.naming.show(&channel.channel_name).into_owned(),
+                    season,
+                    episode,
+                    title: options.naming.title(episode.entry),
+                    filename: options.naming.file_stem(episode.entry),
+                    normalization: options.naming.normalization,
+                };
+                options.layout.channel_dir(base_path, &context)
+            })
+            .unwrap_or_else(|| {
+                let show = options.naming.show(&channel.channel_name);
+                base_path.join(options.naming.normalization.apply(&show).as_ref())
+            });
+        let title_width = channel
+            .seasons
+            .iter()
+            .flat_map(|season| &season.videos)
+            .map(|ep| options.naming.title(ep.entry).chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_TITLE_WIDTH);
+
+        Self {
+            channel,
+            root: base_path.to_path_buf(),
+            base,
+            dry_run: options.dry_run,
+            verbose: options.verbose,
+            options: options.clone(),
+            reflink_warned: Cell::new(false),
+            duplicates: None,
+            targets: RefCell::default(),
+            created: RefCell::default(),
+            verified: RefCell::default(),
+            printed: Cell::new(0),
+            avatar: OnceCell::new(),
+            current: RefCell::default(),
+            title_width,
+            summary: RefCell::new(Summary::default()),
+        }
+    }
+
+    /// Places byte-identical files only once, see [`DuplicateIndex`].
+    pub fn with_duplicates(mut self, duplicates: &'a DuplicateIndex) -> Self {
+        self.duplicates = Some(duplicates);
+        self
+    }
+
+    /// Creates the channel in the target and returns what had to be changed for it.
+    pub fn build(&self, manifest: &mut Manifest) -> anyhow::Result<Summary> {
+        if self.verbose {
+            term::print(format_args!("== {} ==", self.channel.channel_name));
+        }
+
+        self.place(manifest)?;
+        let summary = self.summary.take();
+
+        if self.verbose {
+            let hidden = match self.options.limit_output {
+                Some(limit) if self.printed.get() > limit => {
+                    format!(", {} more lines not shown", self.printed.get() - limit)
+                }
+                _ => String::new(),
+            };
+            term::print(format_args!(
+                "{}: {} files placed, {} directories, {} artwork, {} NFOs{hidden}",
+                self.channel.channel_name,
+                summary.links,
+                summary.directories,
+                summary.artwork,
+                summary.nfo
+            ));
+        }
+
+        Ok(summary)
+    }
+
+    /// Prints a line of verbose output, or only counts and logs it past `--limit-output` lines.
+    fn say(&self, line: std::fmt::Arguments) {
+        let printed = self.printed.get() + 1;
+        self.printed.set(printed);
+
+        if self
+            .options
+            .limit_output
+            .is_none_or(|limit| printed <= limit)
+        {
+            term::print(line);
+        } else {
+            logfile::line(line);
+        }
+    }
+
+    /// Prints what happens to `path` in columns: the action, the episode being placed and the
+    /// path relative to the target, followed by `detail`.
+    fn say_path(&self, action: &str, color: Color, path: &Path, detail: Option<String>) {
+        let path = path.strip_prefix(&self.root).unwrap_or(path).display();
+        match detail {
+            Some(detail) => self.say_line(
+                action,
+                color,
+                format_args!("{path}  {}", term::paint(Color::Dim, detail)),
+            ),
+            None => self.say_line(action, color, format_args!("{path}")),
         }
     }
 
-    pub fn build(&self) -> anyhow::Result<()> {
+    fn say_line(&self, action: &str, color: Color, what: std::fmt::Arguments) {
+        let current = self.current.borrow();
+        let (number, title) = current.as_ref().map_or(("", ""), |(number, title)| {
+            (number.as_str(), title.as_str())
+        });
+        self.say(format_args!(
+            "{:>12} {number:<8} {:<width$}  {what}",
+            term::paint(color, action),
+            term::fit(title, self.title_width),
+            width = self.title_width
+        ));
+    }
+
+    fn place(&self, manifest: &mut Manifest) -> anyhow::Result<()> {
+        let index = self
+            .options
+            .reconcile
+            .then(|| TargetIndex::build(&self.base, &self.root, manifest));
+
         self.create_channel_directory()?;
+        self.write_fanart(manifest)?;
 
         for season in &self.channel.seasons {
-            let season_dir = self.create_season_directory(&season)?;
+            let season_dir = season.videos.first().and_then(|ep| {
+                let context = self.layout_context(season, ep);
+                self.options.layout.season_dir(&self.root, &context)
+            });
+
+            for ep in season.videos.iter() {
+                // Stop between episodes, never in the middle of placing one.
+                if signals::interrupted() {
+                    return Ok(());
+                }
+
+                let context = self.layout_context(season, ep);
+                let (dir, stem) = self.options.layout.locate(&self.root, &context);
+
+                self.create_directory(&dir)?;
+                *self.current.borrow_mut() = Some((
+                    format!("S{:02}E{:02}", season.number, ep.number),
+                    self.options.naming.title(ep.entry).to_string(),
+                ));
+                let stem = self.link_video_data(&dir, stem, ep.entry, index.as_ref(), manifest)?;
+
+                if let Some(stem) = stem
+                    && self.options.nfo
+                {
+                    self.write_nfo(&dir, &stem, season.number, ep, index.as_ref(), manifest)?;
+                }
+                self.current.take();
+            }
+
+            if let Some(season_dir) = season_dir {
+                self.link_season_artwork(season, &season_dir, manifest)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn target_name(&self, entry: &CatalogueEntry, stem: &str, file: &Path) -> OsString {
+        let language = entry
+            .json
+            .language
+            .as_deref()
+            .filter(|language| self.options.tag_subtitle_language && !language.is_empty());
+        entry.target_name(stem, file, language)
+    }
+
+    fn layout_context<'c>(
+        &'c self,
+        season: &'c Season<'a>,
+        episode: &'c Episode<'a>,
+    ) -> LayoutContext<'c> {
+        LayoutContext {
+            channel: self
+                .options
+                .naming
+                .show(&self.channel.channel_name)
+                .into_owned(),
+            season,
+            episode,
+            title: self.options.naming.title(episode.entry),
+            filename: self.options.naming.file_stem(episode.entry),
+            normalization: self.options.naming.normalization,
+        }
+    }
+
+    fn count(&self, update: impl FnOnce(&mut Summary)) {
+        update(&mut self.summary.borrow_mut());
+    }
+
+    fn link_season_artwork(
+        &self,
+        season: &Season<'a>,
+        season_dir: &Path,
+        manifest: &mut Manifest,
+    ) -> anyhow::Result<()> {
+        let Some(artwork) = self.options.season_artwork else {
+            return Ok(());
+        };
 
-            for (ep, vid) in season.videos.iter().enumerate() {
-                self.link_video_data(&season_dir, ep + 1, &vid)?;
+        let mut candidates = season.videos.iter().filter_map(|ep| {
+            ep.entry
+                .get_thumbnail()
+                .map(|thumbnail| (ep.entry, thumbnail))
+        });
+
+        let picked = match artwork {
+            SeasonArtwork::First => candidates.next(),
+            SeasonArtwork::MostViewed => {
+                candidates.max_by_key(|(v, _)| v.json.view_count.unwrap_or(0))
             }
+        };
+
+        let Some((entry, thumbnail)) = picked else {
+            return Ok(());
+        };
+
+        let ext = thumbnail.extension().unwrap().to_string_lossy();
+        let target = match self.options.season_artwork_name {
+            SeasonArtworkName::Folder => season_dir.join(format!("folder.{ext}")),
+            SeasonArtworkName::Poster => self
+                .base
+                .join(format!("season{:0>2}-poster.{ext}", season.number)),
+        };
+
+        self.create_link(thumbnail, &target, entry)?;
+        self.record_link(manifest, &target, thumbnail, &entry.json.id);
+
+        Ok(())
+    }
+
+    fn write_fanart(&self, manifest: &mut Manifest) -> anyhow::Result<()> {
+        if self.options.artwork != Artwork::Download {
+            return Ok(());
+        }
+
+        let picked = self
+            .channel
+            .seasons
+            .iter()
+            .flat_map(|s| s.videos.iter())
+            .map(|ep| ep.entry)
+            .rev()
+            .find_map(|v| artwork::largest(&v.json.thumbnails).map(|t| (v, t)));
+
+        let Some((entry, thumbnail)) = picked else {
+            return Ok(());
+        };
+
+        let target = self.base.join(format!("fanart.{}", thumbnail.extension()));
+        if target.exists() {
+            return Ok(());
+        }
+
+        if self.verbose {
+            self.say_path("Writing", Color::Green, &target, None);
         }
 
+        if self.dry_run {
+            self.count(|s| s.artwork += 1);
+            self.options.events.emit(Event::Artwork { path: &target });
+            self.options.plan.record(|| Operation::Download {
+                id: entry.json.id.clone(),
+                thumbnail: thumbnail.clone(),
+                target,
+            });
+            return Ok(());
+        }
+
+        let cached = match artwork::fetch(&entry.json.id, thumbnail) {
+            Ok(cached) => cached,
+            Err(err) => {
+                let message = format!(
+                    "Can't download artwork for {:?}: {err}",
+                    self.channel.channel_name
+                );
+                term::error(Color::Red, message);
+                return Ok(());
+            }
+        };
+
+        std::fs::copy(&cached, &target)?;
+        self.record_link(manifest, &target, &cached, &entry.json.id);
+        self.count(|s| s.artwork += 1);
+        self.options.events.emit(Event::Artwork { path: &target });
+
         Ok(())
     }
 
+    fn record_link(&self, manifest: &mut Manifest, target: &Path, source: &Path, id: &str) {
+        let sha256 = self.verified.borrow_mut().remove(target);
+        if let Ok(link) = target.strip_prefix(&self.root) {
+            manifest.record(link.to_path_buf(), source.to_path_buf(), id, sha256);
+        }
+    }
+
     fn link_video_data(
         &self,
-        season_dir: &PathBuf,
-        ep_no: usize,
+        season_dir: &Path,
+        stem: String,
         entry: &'a CatalogueEntry,
+        index: Option<&TargetIndex>,
+        manifest: &mut Manifest,
+    ) -> anyhow::Result<Option<String>> {
+        let mut base_file_name = stem;
+        let links_before = self.summary.borrow().links;
+
+        if let Some(renamed) = self.renamed_file_stem(season_dir, &base_file_name, entry, manifest)
+        {
+            base_file_name = renamed;
+        }
+
+        let conflicts = self
+            .placed_files(entry)
+            .filter_map(|file| {
+                let target = season_dir.join(self.target_name(entry, &base_file_name, file));
+                self.conflict(&target, file, &entry.json.id, manifest)
+                    .map(|reason| (target, reason))
+            })
+            .collect::<Vec<_>>();
+
+        let mut taken = Vec::new();
+        if !conflicts.is_empty() {
+            let reasons = conflicts.iter().map(|(_, r)| r.clone()).collect::<Vec<_>>();
+            let title = self.options.naming.title(entry);
+            let mut resolution = self.options.conflicts.resolve(&title, &reasons)?;
+            // An answer to `--prompt` was already given on the terminal.
+            if resolution == Resolution::Replace
+                && !self.dry_run
+                && !self.options.conflicts.prompts()
+                && !confirm::confirm(&format!(
+                    "Replace what's in the way of {title:?} ({})?",
+                    reasons.join("; ")
+                ))?
+            {
+                resolution = Resolution::Keep;
+            }
+            match resolution {
+                Resolution::Keep => {
+                    if self.verbose {
+                        for reason in &reasons {
+                            self.say_line("Keeping", Color::Yellow, format_args!("{reason}"));
+                        }
+                    }
+                    taken = conflicts.into_iter().map(|(target, _)| target).collect();
+                }
+                Resolution::Replace => {
+                    for (target, _) in &conflicts {
+                        self.remove_file(target)?;
+                    }
+                }
+                Resolution::Rename => {
+                    base_file_name = self.free_file_stem(season_dir, &base_file_name, entry);
+                }
+                Resolution::Skip => {
+                    if self.verbose {
+                        self.say_line("Skipping", Color::Yellow, format_args!(""));
+                    }
+                    return Ok(None);
+                }
+                Resolution::Fail => {
+                    Err(OrganizerError::CollisionError {
+                        title: self.options.naming.title(entry),
+                        reasons,
+                    })?;
+                }
+            }
+        }
+
+        for file in self.placed_files(entry) {
+            let target = season_dir.join(self.target_name(entry, &base_file_name, file));
+            if taken.contains(&target) {
+                continue;
+            }
+
+            let adopted = index
+                .filter(|_| target.symlink_metadata().is_err())
+                .and_then(|index| index.find(file, &entry.json.id));
+
+            let target = match adopted {
+                Some(existing) => {
+                    if self.verbose {
+                        let renamed = target.strip_prefix(&self.root).unwrap_or(&target);
+                        self.say_path(
+                            "Adopting",
+                            Color::Green,
+                            existing,
+                            Some(format!("renamed from {}", renamed.display())),
+                        );
+                    }
+
+                    if let Ok(link) = target.strip_prefix(&self.root) {
+                        manifest.remove(link);
+                    }
+
+                    existing.clone()
+                }
+                None => {
+                    self.create_link(file, &target, entry)?;
+                    if signals::interrupted() && target.symlink_metadata().is_err() {
+                        break;
+                    }
+                    target
+                }
+            };
+
+            self.targets
+                .borrow_mut()
+                .insert(target.clone(), entry.json.id.clone());
+            self.record_link(manifest, &target, file, &entry.json.id);
+        }
+
+        if self.summary.borrow().links > links_before {
+            self.count(|s| s.new_episodes.push(self.options.naming.title(entry)));
+        }
+
+        Ok(Some(base_file_name))
+    }
+
+    /// Where the files of an episode go, before conflicts with files already in the target are
+    /// resolved. Generated NFOs have no source.
+    fn planned_files(
+        &self,
+        season: &Season<'a>,
+        ep: &Episode<'a>,
+        manifest: &Manifest,
+    ) -> Vec<(Option<&'a PathBuf>, PathBuf)> {
+        let context = self.layout_context(season, ep);
+        let (dir, stem) = self.options.layout.locate(&self.root, &context);
+        let stem = self
+            .renamed_file_stem(&dir, &stem, ep.entry, manifest)
+            .unwrap_or(stem);
+
+        let mut files = self
+            .placed_files(ep.entry)
+            .map(|file| {
+                (
+                    Some(file),
+                    dir.join(self.target_name(ep.entry, &stem, file)),
+                )
+            })
+            .collect::<Vec<_>>();
+        if self.options.nfo {
+            files.push((None, dir.join(format!("{stem}.nfo"))));
+        }
+
+        files
+    }
+
+    /// Files of the episode that end up in the target. With NFOs generated, the info.json and
+    /// `.description` only feed the NFO unless `link_metadata_sidecars` is set, and a downloaded
+    /// `.nfo` would clash with the generated one.
+    fn placed_files<'e>(&self, entry: &'e CatalogueEntry) -> impl Iterator<Item = &'e PathBuf> {
+        let nfo = self.options.nfo;
+        let link_metadata = self.options.link_metadata_sidecars;
+
+        entry.path.iter().filter(move |file| {
+            let generated = nfo && file.extension().is_some_and(|ext| ext == "nfo");
+            let metadata = nfo && !link_metadata && entry.is_metadata(file);
+
+            !generated && !metadata
+        })
+    }
+
+    /// The channel avatar from the channel's info.json in the source directories of its videos.
+    fn avatar(&self) -> Option<&str> {
+        self.avatar
+            .get_or_init(|| {
+                let known = self
+                    .channel
+                    .seasons
+                    .iter()
+                    .flat_map(|season| &season.videos)
+                    .filter_map(|ep| ep.entry.info_json())
+                    .map(PathBuf::as_path)
+                    .collect::<HashSet<_>>();
+                let dirs = known
+                    .iter()
+                    .filter_map(|info_json| info_json.parent())
+                    .unique();
+                artwork::channel_avatar(dirs, &known)
+            })
+            .as_deref()
+    }
+
+    fn write_nfo(
+        &self,
+        season_dir: &Path,
+        stem: &str,
+        season: usize,
+        ep: &Episode<'a>,
+        index: Option<&TargetIndex>,
+        manifest: &mut Manifest,
     ) -> anyhow::Result<()> {
-        let base_file_name = format!("{}", entry.get_title().replace("/", "_"));
+        let planned = season_dir.join(format!("{stem}.nfo"));
+        // An episode adopted under another name keeps its NFO next to its media.
+        let id = &ep.entry.json.id;
+        let adopted = index
+            .filter(|_| planned.symlink_metadata().is_err())
+            .and_then(|index| {
+                let info_json = ep.entry.info_json().map(PathBuf::as_path);
+                let media = || ep.entry.get_media().next().and_then(|m| index.find(m, id));
+                index
+                    .find_nfo(info_json, id)
+                    .cloned()
+                    .or_else(|| Some(media()?.with_extension("nfo")))
+            });
+        let target = adopted.unwrap_or(planned);
+        let description = match ep.entry.get_description() {
+            // Descriptions aren't always valid UTF-8, a broken character shouldn't stop the run.
+            Some(path) => Some(Cow::Owned(
+                String::from_utf8_lossy(&std::fs::read(path)?).into_owned(),
+            )),
+            None => ep.entry.json.description.as_deref().map(Cow::Borrowed),
+        };
+        let title = self.options.naming.title(ep.entry);
+        let plot = description.as_deref().filter(|d| !d.trim().is_empty());
+        let actors = Actor::of(ep.entry, self.avatar());
+        let content = if self.options.music_video {
+            MusicVideoNfo {
+                entry: ep.entry,
+                title: &title,
+                artist: &self.options.naming.show(&self.channel.channel_name),
+                plot,
+                actors: &actors,
+            }
+            .render()
+        } else {
+            EpisodeNfo {
+                entry: ep.entry,
+                title: &title,
+                season,
+                episode: ep.number,
+                plot,
+                actors: &actors,
+            }
+            .render()
+        };
+
+        if std::fs::read_to_string(&target).is_ok_and(|existing| existing == content) {
+            return Ok(());
+        }
 
-        for file in entry.path.iter() {
-            let mut base_file_name = OsString::from(base_file_name.clone());
-            let ext: OsString = file.extension().unwrap().into();
+        self.count(|s| s.nfo += 1);
+        self.options.events.emit(Event::Nfo { path: &target });
 
-            base_file_name.push(".");
-            base_file_name.push(ext);
+        if self.verbose {
+            self.say_path("Writing", Color::Green, &target, None);
+        }
 
-            let mut target = season_dir.clone();
-            target.push(base_file_name);
+        if let Some(info_json) = ep.entry.info_json() {
+            self.record_link(manifest, &target, info_json, &ep.entry.json.id);
+        }
 
-            let target = PathBuf::from(target);
-            self.create_symlink(file, &target)?;
+        if self.dry_run {
+            self.options.plan.record(|| Operation::Write {
+                path: target,
+                content,
+            });
+            return Ok(());
         }
 
+        std::fs::write(&target, content)?;
+
         Ok(())
     }
 
-    fn create_symlink(&self, source: &PathBuf, target: &PathBuf) -> anyhow::Result<()> {
-        if self.dry_run || self.verbose {
-            println!("Linking: {source:?} -> {target:?}");
+    /// Describes why `target` can't simply be linked to `source`: it is taken by another episode
+    /// of this run, by a link to another video or by a file the organizer doesn't know about.
+    fn conflict(
+        &self,
+        target: &Path,
+        source: &Path,
+        id: &str,
+        manifest: &Manifest,
+    ) -> Option<String> {
+        if let Some(other) = self.targets.borrow().get(target)
+            && other != id
+        {
+            return Some(format!("{target:?} is also the name of video {other}"));
+        }
+
+        let meta = target.symlink_metadata().ok()?;
 
-            if self.dry_run {
-                return Ok(());
+        if let Some(known) = target
+            .strip_prefix(&self.root)
+            .ok()
+            .and_then(|link| manifest.links.get(link))
+        {
+            return (known.id != id)
+                .then(|| format!("{target:?} already holds video {}", known.id));
+        }
+
+        if !meta.is_symlink() {
+            return Some(format!(
+                "{target:?} exists and wasn't created by the organizer"
+            ));
+        }
+
+        let points_to = std::fs::read_link(target).ok()?;
+        let canonical = self.duplicates.and_then(|d| d.canonical(source));
+        if points_to == source
+            || points_to == self.link_target(source)
+            || Some(&points_to) == canonical
+        {
+            None
+        } else {
+            Some(format!("{target:?} links to {points_to:?}"))
+        }
+    }
+
+    /// Name the episode got when a conflict was resolved by renaming it in a previous run.
+    fn renamed_file_stem(
+        &self,
+        season_dir: &Path,
+        stem: &str,
+        entry: &CatalogueEntry,
+        manifest: &Manifest,
+    ) -> Option<String> {
+        let media = entry.get_media().next()?;
+
+        (2..)
+            .map(|n| format!("{stem} ({n})"))
+            .map(|candidate| {
+                let target = season_dir.join(self.target_name(entry, &candidate, media));
+                (candidate, target)
+            })
+            .take_while(|(_, target)| target.symlink_metadata().is_ok())
+            .find(|(_, target)| {
+                target
+                    .strip_prefix(&self.root)
+                    .ok()
+                    .and_then(|link| manifest.links.get(link))
+                    .is_some_and(|known| known.id == entry.json.id)
+            })
+            .map(|(candidate, _)| candidate)
+    }
+
+    /// First `stem (N)` none of the episode files would collide with.
+    fn free_file_stem(&self, season_dir: &Path, stem: &str, entry: &CatalogueEntry) -> String {
+        (2..)
+            .map(|n| format!("{stem} ({n})"))
+            .find(|candidate| {
+                self.placed_files(entry).all(|file| {
+                    let target = season_dir.join(self.target_name(entry, candidate, file));
+                    target.symlink_metadata().is_err()
+                        && !self.targets.borrow().contains_key(&target)
+                })
+            })
+            .unwrap()
+    }
+
+    fn remove_file(&self, target: &Path) -> anyhow::Result<()> {
+        if self.verbose {
+            self.say_path("Removing", Color::Yellow, target, None);
+        }
+
+        if self.dry_run {
+            self.options.plan.record(|| Operation::Remove {
+                path: target.to_path_buf(),
+            });
+            return Ok(());
+        }
+
+        match self.retry(target, || trash::remove(target, &self.root)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(OrganizerError::io(target)(err))?,
+            _ => Ok(()),
+        }
+    }
+
+    fn create_link(
+        &self,
+        source: &Path,
+        target: &Path,
+        entry: &CatalogueEntry,
+    ) -> anyhow::Result<()> {
+        let (source, hard_link) = match (self.duplicates, self.options.link_mode) {
+            (None, _) => (source, None),
+            (Some(duplicates), LinkMode::Symlink) => (
+                duplicates.canonical(source).map_or(source, |c| c.as_path()),
+                None,
+            ),
+            (Some(duplicates), _) => (source, duplicates.placed(source, &self.root)),
+        };
+        let exists = target.symlink_metadata().is_ok();
+
+        let (action, mode) = match (&hard_link, self.options.link_mode) {
+            (Some(_), _) => ("Hard linking", "hardlink"),
+            (None, LinkMode::Symlink) => ("Linking", "symlink"),
+            (None, LinkMode::Reflink) => ("Cloning", "reflink"),
+            (None, LinkMode::Copy) => ("Copying", "copy"),
+        };
+        let from = hard_link.as_deref().unwrap_or(source);
+
+        if self.verbose {
+            self.say_path(
+                action,
+                Color::Green,
+                target,
+                Some(format!("<- {}", from.display())),
+            );
+        }
+
+        if self.dry_run {
+            self.mark_placed(source, target);
+            if !exists {
+                self.linked(from, target, mode);
+                self.options.plan.record(|| match &hard_link {
+                    Some(existing) => Operation::HardLink {
+                        existing: existing.clone(),
+                        target: target.to_path_buf(),
+                        id: entry.json.id.clone(),
+                        kind: entry.kind,
+                    },
+                    None => Operation::Link {
+                        source: source.to_path_buf(),
+                        target: target.to_path_buf(),
+                        mode: self.options.link_mode,
+                        points_to: match self.link_target(source) {
+                            Cow::Owned(mapped) => Some(mapped),
+                            Cow::Borrowed(_) => None,
+                        },
+                        id: entry.json.id.clone(),
+                        kind: entry.kind,
+                    },
+                });
             }
+            return Ok(());
         }
 
-        match std::os::unix::fs::symlink(source, target) {
-            Ok(_) => {}
+        let result = self.retry(target, || match (&hard_link, self.options.link_mode) {
+            (Some(existing), _) => std::fs::hard_link(existing, target),
+            (None, LinkMode::Symlink) => {
+                std::os::unix::fs::symlink(self.link_target(source), target)
+            }
+            (None, LinkMode::Reflink) => self.reflink_or_copy(source, target),
+            (None, LinkMode::Copy) => copy_file(source, target),
+        });
+
+        match result {
+            Ok(_) => {
+                if hard_link.is_none()
+                    && self.options.link_mode == LinkMode::Copy
+                    && self.options.verify_copies
+                {
+                    self.verify_copy(source, target)?;
+                }
+                self.linked(from, target, mode)
+            }
+            // A throttled copy stopped halfway, the run winds down before the next file.
+            Err(_) if signals::interrupted() => return Ok(()),
             Err(err) => {
                 if err.kind() != ErrorKind::AlreadyExists {
-                    Err(err)?;
-                } else {
-                    return Ok(());
+                    Err(OrganizerError::io(target)(err))?;
                 }
             }
         }
 
+        self.mark_placed(source, target);
+
         Ok(())
     }
 
-    fn create_season_directory(&self, season: &Season<'a>) -> anyhow::Result<PathBuf> {
-        let season_dir = {
-            let mut d = self.base.clone();
-            d.push(format!("Season {}", season.number));
+    /// What a symlink to `source` holds, see `--link-prefix-map`.
+    fn link_target<'p>(&self, source: &'p Path) -> Cow<'p, Path> {
+        prefix::link_target(&self.options.link_prefix_map, source)
+    }
 
-            d
-        };
+    /// Hashes a fresh copy and its source, removing the copy when they differ so the next run
+    /// copies it again.
+    fn verify_copy(&self, source: &Path, target: &Path) -> anyhow::Result<()> {
+        let expected = dedupe::hash_file(source)?;
+        let copied = dedupe::hash_file(target)?;
+        if copied != expected {
+            std::fs::remove_file(target).map_err(OrganizerError::io(target))?;
+            bail!(
+                "The copy {target:?} doesn't match {source:?} (SHA-256 {copied} instead of \
+                 {expected}), the target disk may be failing"
+            );
+        }
 
-        if self.dry_run || self.verbose {
-            println!("Creating directory: {:?}", season_dir);
+        if self.verbose {
+            self.say_path(
+                "Verified",
+                Color::Green,
+                target,
+                Some(format!("SHA-256 {expected}")),
+            );
+        }
+        self.verified
+            .borrow_mut()
+            .insert(target.to_path_buf(), expected);
 
-            if self.dry_run {
-                return Ok(season_dir);
-            }
+        Ok(())
+    }
+
+    fn linked(&self, source: &Path, target: &Path, mode: &str) {
+        self.count(|s| s.links += 1);
+        self.options.events.emit(Event::Linked {
+            source,
+            target,
+            mode,
+        });
+    }
+
+    fn mark_placed(&self, source: &Path, target: &Path) {
+        if let Some(duplicates) = self.duplicates {
+            duplicates.mark_placed(source, &self.root, target);
         }
+    }
 
-        std::fs::create_dir_all(&season_dir)?;
+    fn reflink_or_copy(&self, source: &Path, target: &Path) -> std::io::Result<()> {
+        match reflink::reflink(source, target) {
+            Err(err) if err.kind() != ErrorKind::AlreadyExists => {
+                if !self.reflink_warned.replace(true) {
+                    let message = format!("Can't reflink {source:?} ({err}), copying instead");
+                    term::error(Color::Yellow, message);
+                }
 
-        Ok(season_dir)
+                copy_file(source, target)
+            }
+            result => result,
+        }
     }
 
     fn create_channel_directory(&self) -> anyhow::Result<()> {
-        if self.dry_run || self.verbose {
-            println!("Creating directory: {:?}", self.base);
+        self.create_directory(&self.base)
+    }
 
-            if self.dry_run {
-                return Ok(());
-            }
+    fn create_directory(&self, dir: &Path) -> anyhow::Result<()> {
+        if !self.created.borrow_mut().insert(dir.to_path_buf()) {
+            return Ok(());
         }
 
-        std::fs::create_dir_all(&self.base)?;
+        if !dir.is_dir() {
+            self.count(|s| s.directories += 1);
+            self.options.events.emit(Event::Directory { path: dir });
+            self.options.plan.record(|| Operation::CreateDir {
+                path: dir.to_path_buf(),
+            });
+        }
+
+        if self.verbose {
+            self.say_path("Creating", Color::Green, dir, None);
+        }
+
+        if self.dry_run {
+            return Ok(());
+        }
+
+        self.retry(dir, || std::fs::create_dir_all(dir))
+            .map_err(OrganizerError::io(dir))?;
 
         Ok(())
     }
+
+    /// Runs a filesystem operation on `path`, repeating it with a growing delay while it fails
+    /// with a transient error, up to `retries` more times.
+    fn retry<T>(
+        &self,
+        path: &Path,
+        mut op: impl FnMut() -> std::io::Result<T>,
+    ) -> std::io::Result<T> {
+        let mut delay = RETRY_DELAY;
+        let mut attempts = 0;
+
+        loop {
+            match op() {
+                Err(err) if attempts < self.options.retries && is_transient(&err) => {
+                    attempts += 1;
+                    if self.verbose {
+                        self.say_path(
+                            "Retrying",
+                            Color::Yellow,
+                            path,
+                            Some(format!("in {delay:?}: {err}")),
+                        );
+                    }
+                    if !signals::sleep(delay) {
+                        return Err(err);
+                    }
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Titles longer than this are cut short in verbose output, keeping the paths after them aligned.
+const MAX_TITLE_WIDTH: usize = 40;
+
+/// Wait before the first retry of a failed filesystem operation.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Errors network filesystems return while a server is busy or a file handle went stale, which
+/// usually succeed when repeated.
+fn is_transient(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    ) || err.raw_os_error().is_some_and(|code| {
+        [
+            libc::EAGAIN,
+            libc::ESTALE,
+            libc::EBUSY,
+            libc::EINTR,
+            libc::ETIMEDOUT,
+        ]
+        .contains(&code)
+    })
+}
+
+/// Writes `value` as pretty JSON through a temporary file renamed into place, so an interrupted
+/// run never leaves a truncated file behind.
+pub fn write_json(path: &Path, value: &impl serde::Serialize) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut writer = std::io::BufWriter::new(File::create(&tmp)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    drop(writer);
+
+    std::fs::rename(tmp, path)?;
+
+    Ok(())
+}
+
+/// Emby and Jellyfin skip directories containing this file when scanning a library.
+const LIBRARY_IGNORE_MARKER: &str = ".ignore";
+
+/// Marks a directory the organizer keeps inside the target for its own files, so media servers
+/// never scan it as a show.
+fn hide_from_library(
+    dir: &Path,
+    dry_run: bool,
+    verbose: bool,
+    plan: &Recorder,
+) -> anyhow::Result<()> {
+    let marker = dir.join(LIBRARY_IGNORE_MARKER);
+    if marker.exists() {
+        return Ok(());
+    }
+
+    if verbose {
+        term::say(Color::Green, "Hiding", dir.display());
+    }
+
+    if dry_run {
+        plan.record(|| Operation::Write {
+            path: marker,
+            content: String::new(),
+        });
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(dir)?;
+    File::create(marker)?;
+
+    Ok(())
+}
+
+/// Copies through a temporary file in the target directory so a partially written file never
+/// shows up under its final name.
+fn copy_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    if target.symlink_metadata().is_ok() {
+        return Err(ErrorKind::AlreadyExists.into());
+    }
+
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(target.file_name().unwrap_or_default());
+    tmp_name.push(".part");
+    let tmp = target.with_file_name(tmp_name);
+
+    throttle::copy(source, &tmp)?;
+    std::fs::rename(&tmp, target)
+}
+
+/// Exit code telling scripts that the target was (or in a dry run would be) changed. Errors exit
+/// with 1 and runs with nothing to do with 0.
+const EXIT_CHANGED: u8 = 2;
+
+fn main() -> ExitCode {
+    match organise() {
+        Ok(code) => code,
+        Err(err) => {
+            logfile::line(format_args!("Error: {err:#}"));
+            eprintln!("{} {err:?}", term::paint_err(Color::Red, "Error:"));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn organise() -> anyhow::Result<ExitCode> {
+    let mut cli = Cli::parse();
+    term::init(cli.color);
+    if cli.check_only {
+        if let Some(
+            Command::Plan { .. }
+            | Command::Snapshot { .. }
+            | Command::State { .. }
+            | Command::Daemon { .. },
+        ) = cli.command
+        {
+            bail!("This command writes files, it can't run with --check-only");
+        }
+        if cli.events_file.is_some() || cli.metrics_file.is_some() || cli.log_file.is_some() {
+            bail!(
+                "--events-file, --metrics-file and --log-file write files, --check-only can't \
+                 use them"
+            );
+        }
+        cli.dry_run = true;
+    }
+    if let Some(limit) = cli.bwlimit {
+        throttle::set_limit(limit.0);
+    }
+    if let Some(place) = cli.trash {
+        trash::set(place);
+    }
+    if cli.yes {
+        confirm::assume_yes();
+    }
+    if let Some(path) = &cli.log_file {
+        logfile::open(path, cli.log_max_size.0, cli.log_keep)
+            .with_context(|| format!("Can't open the log file {path:?}"))?;
+    }
+    if let Some(Command::Doctor) = cli.command {
+        // Runs before loading the config, a broken one is one of the things it reports.
+        doctor::run(&cli)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let config = Config::load(cli.config.as_deref())?;
+    let events = Events::open(cli.events, cli.events_file.as_ref(), cli.dry_run)?;
+    signals::install();
+
+    if let Some(Command::Daemon {
+        interval,
+        schedule,
+        listen,
+    }) = cli.command.clone()
+    {
+        // Nobody is there to answer.
+        confirm::assume_yes();
+        let timing = match schedule {
+            Some(schedule) => Timing::Cron(schedule),
+            None => Timing::Every(interval.0),
+        };
+        let mut cli = cli;
+        cli.command = Some(Command::Organize);
+        daemon::run(timing, listen, || {
+            run_reported(cli.clone(), &config, &events)
+        })?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // yt-dlp treats any non-zero exit of an --exec command as a failed download.
+    let hook = matches!(cli.command, Some(Command::OrganizeOne { .. }));
+    let check_only = cli.check_only && cli.events.is_none();
+    let (report, result) = run_reported(cli, &config, &events);
+
+    if signals::interrupted() {
+        let total = &report.total;
+        let message = format!(
+            "Interrupted: {} files placed, {} directories created, {} NFOs written and {} new \
+             episodes before stopping",
+            total.links, total.directories, total.nfo, report.new_episodes
+        );
+        logfile::line(&message);
+        eprintln!("{message}");
+        return Ok(ExitCode::from(signals::EXIT_INTERRUPTED));
+    }
+
+    result?;
+
+    if check_only {
+        println!(
+            "{}",
+            match report.has_changes() {
+                true => "Changes pending",
+                false => "Up to date",
+            }
+        );
+    }
+    if report.has_changes() && !hook {
+        Ok(ExitCode::from(EXIT_CHANGED))
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Runs once and sends the report to the webhook, metrics file and notification targets.
+fn run_reported(cli: Cli, config: &Config, events: &Events) -> (Report, anyhow::Result<()>) {
+    let started = Instant::now();
+    let webhook_url = cli.webhook_url.clone();
+    let metrics_file = cli.metrics_file.clone();
+    let mut report = Report {
+        dry_run: cli.dry_run,
+        ..Default::default()
+    };
+    logfile::line(format_args!(
+        "Run started: {}",
+        std::env::args().collect::<Vec<_>>().join(" ")
+    ));
+
+    let result = run(cli, config, events, &mut report);
+
+    // A cron user lacking permissions would otherwise only see videos go missing.
+    if !report.inaccessible.is_empty() {
+        let paths = report
+            .inaccessible
+            .iter()
+            .map(|unreadable| format!("\n  {:?}: {}", unreadable.path, unreadable.error))
+            .collect::<String>();
+        let count = match report.inaccessible.len() {
+            1 => "1 path".to_string(),
+            n => format!("{n} paths"),
+        };
+        term::error(
+            Color::Yellow,
+            format_args!("Left out {count} of the source that couldn't be read:{paths}"),
+        );
+    }
+
+    if let Err(err) = &result {
+        let message = format!("{err:#}");
+        events.emit(Event::Error {
+            message: message.clone(),
+            category: OrganizerError::category_of(err),
+        });
+        report.errors.push(message);
+    }
+
+    if let Some(url) = webhook_url
+        && let Err(err) = notify::webhook(&url, &report)
+    {
+        let message = format!("Can't post the run summary to {url}: {err}");
+        term::error(Color::Red, message);
+    }
+
+    if let Some(path) = metrics_file
+        && let Err(err) = metrics::write(&path, &report, started.elapsed())
+    {
+        let message = format!("Can't write metrics to {path:?}: {err}");
+        term::error(Color::Red, message);
+    }
+
+    for notification in &config.notifications {
+        if let Err(err) = notify::send(notification, &report) {
+            let message = format!("Can't send notification: {err}");
+            term::error(Color::Red, message);
+        }
+    }
+
+    let total = &report.total;
+    logfile::line(format_args!(
+        "Run finished in {:.1?}: {} files placed, {} directories created, {} NFOs written, {} new \
+         episodes, {} errors, {} unreadable paths",
+        started.elapsed(),
+        total.links,
+        total.directories,
+        total.nfo,
+        report.new_episodes,
+        report.errors.len(),
+        report.inaccessible.len()
+    ));
+
+    (report, result)
+}
+
+/// The info.json belonging to `path`, which may be the info.json itself or the downloaded media.
+fn info_json_for(path: &Path) -> PathBuf {
+    if is_info_json(path) {
+        path.to_path_buf()
+    } else {
+        path.with_extension("info.json")
+    }
+}
+
+/// Reads a list of paths, one per line, from a file or from stdin when `list` is `-`.
+fn read_paths(list: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let lines = if list == Path::new("-") {
+        std::io::stdin().lines().collect::<Result<Vec<_>, _>>()?
+    } else {
+        std::fs::read_to_string(list)
+            .with_context(|| format!("Can't read paths from {list:?}"))?
+            .lines()
+            .map(String::from)
+            .collect()
+    };
+
+    Ok(lines
+        .iter()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// `options` adjusted to the mode of a channel in the config.
+fn channel_options(
+    options: &BuilderOptions,
+    config: &Config,
+    channel: &str,
+) -> anyhow::Result<BuilderOptions> {
+    let mut options = options.clone();
+    if config
+        .channels
+        .get(channel)
+        .is_some_and(|channel| channel.mode == ChannelMode::MusicVideo)
+    {
+        options.layout = layout::MUSIC_VIDEO_LAYOUT.parse()?;
+        options.music_video = true;
+        options.nfo = true;
+    }
+
+    Ok(options)
 }
 
-fn main() -> Result<(), anyhow::Error> {
-    let cli = Cli::parse();
+/// A target root together with the channels placed into it.
+struct Library<'a> {
+    /// For a remote target its local staging directory
+    target: Option<PathBuf>,
+    remote: Option<Remote>,
+    structure: Vec<SeasonedStructure<'a>>,
+    /// Numbers handed out in previous runs, with `--stable-numbering`
+    state: Option<State>,
+}
+
+fn run(cli: Cli, config: &Config, events: &Events, report: &mut Report) -> anyhow::Result<()> {
+    // Human readable progress would corrupt an event stream written to stdout, it only goes to
+    // the log file then.
+    let console = cli.events.is_none() || cli.events_file.is_some();
+    term::set_console(console);
+    let verbose = console || logfile::enabled();
+    let routes = config.routes(&cli.route);
+
+    if let Some(Command::Apply { plan }) = &cli.command {
+        report.total = plan::apply(plan, cli.dry_run, cli.force, verbose)?;
+        return Ok(());
+    }
+
+    let library_subdir = cli
+        .library_subdir
+        .as_deref()
+        .or(config.library_subdir.as_deref());
+    if let Some(subdir) = library_subdir
+        && !subdir
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        bail!("--library-subdir {subdir:?} must be a relative path inside the target");
+    }
+    let library_root = |target: &Path| match library_subdir {
+        Some(subdir) => target.join(subdir),
+        None => target.to_path_buf(),
+    };
+
+    if let Some(Command::State { action }) = &cli.command {
+        let target =
+            cli.target.first().or(config.target.as_ref()).context(
+                "The state is kept in the target, pass --target or set it in the config",
+            )?;
+        let target = library_root(target);
+        let target = match Remote::parse(&target)? {
+            Some(remote) => remote.staging_dir(),
+            None => target,
+        };
+        return backup::run(action, &target, verbose);
+    }
+
+    if let Some(Command::EmptyTrash { expired }) = &cli.command {
+        let days = expired.then_some(cli.trash_days);
+        let mut roots = vec![cli.source.clone()];
+        roots.extend(
+            cli.target
+                .iter()
+                .chain(&config.target)
+                .chain(routes.iter().map(|route| &route.target))
+                .map(|target| library_root(target)),
+        );
+        let roots = roots.into_iter().unique().collect::<Vec<_>>();
+        let mut trashed = 0;
+        for root in &roots {
+            trashed += trash::empty(root, days, true, false)?;
+        }
+        if !cli.dry_run
+            && trashed > 0
+            && !confirm::confirm(&format!("Delete {trashed} files from the trash for good?"))?
+        {
+            return Ok(());
+        }
 
-    let cat = VideoCatalogue::build(cli.source)?;
-    let structure = cat.build_seasons();
+        let mut deleted = 0;
+        for root in &roots {
+            deleted += trash::empty(root, days, cli.dry_run, verbose)?;
+        }
+        if verbose {
+            term::print(format_args!("Deleted {deleted} files from the trash"));
+        }
+        return Ok(());
+    }
+
+    let planning = matches!(cli.command, Some(Command::Plan { .. }));
+    let target = cli.target.first().or(config.target.as_ref()).cloned();
+    let refreshing = matches!(cli.command, Some(Command::RefreshMetadata { .. }));
+    let organize = planning
+        || matches!(
+            cli.command,
+            None | Some(Command::Organize | Command::Migrate)
+        )
+        || (refreshing && (target.is_some() || !routes.is_empty()));
+    if organize && target.is_none() && routes.is_empty() && cli.dedupe != Some(Dedupe::Report) {
+        bail!(
+            "Nothing to organize into, pass --target or set \"target\" in the config \
+             (or use `list` to only print the structure)"
+        );
+    }
+
+    let draw_tree = cli.format == Some(OutputFormat::Tree);
+    match cli.format {
+        Some(OutputFormat::Tree) if organize && !planning && !cli.dry_run => {
+            bail!("--format tree only draws the changes of a --dry-run")
+        }
+        Some(format) if organize && format != OutputFormat::Tree => {
+            bail!("--format json and table are for `list` and `stats`")
+        }
+        _ => {}
+    }
 
-    if let Some(target) = cli.target {
-        for chan in structure {
-            DirectoryBuilder::new(&target, chan, cli.dry_run).build()?;
+    let catalogue_options = CatalogueOptions {
+        verbose: verbose && organize && !draw_tree,
+        events: events.clone(),
+        date_format: cli.date_format,
+        output_templates: cli.output_template,
+        sidecars: SidecarFilter::new(cli.sidecar_allow, cli.sidecar_deny),
+        follow_symlinks: cli.follow_symlinks,
+        max_depth: cli.max_depth,
+        same_filesystem: cli.same_filesystem,
+        scan_threads: cli.scan_threads,
+        parse_filenames: cli.parse_filenames,
+        classifier: Classifier::new(&config.channels),
+    };
+
+    let paths = cli.paths_from.as_deref().map(read_paths).transpose()?;
+    let scan = || match &paths {
+        Some(paths) => VideoCatalogue::from_paths(&cli.source, paths.clone(), &catalogue_options),
+        None => VideoCatalogue::build(cli.source.clone(), &catalogue_options),
+    };
+    let mut cat = scan()?;
+    if let Some(Command::RefreshMetadata { ids, yt_dlp }) = &cli.command {
+        let refreshed = refresh::run(&cat, ids, yt_dlp, cli.dry_run, verbose)?;
+        if verbose {
+            term::print(format_args!("Refreshed the metadata of {refreshed} videos"));
+        }
+        // Media organized from its file name now has an info.json, which a list of paths
+        // doesn't name.
+        if refreshed > 0 && !cli.dry_run {
+            cat = scan()?;
+        }
+        if !organize {
+            return Ok(());
+        }
+    }
+    if let Some(Command::Channels { generate_config }) = cli.command {
+        let channels = channels::collect(&cat, config);
+        if generate_config {
+            return channels::print_config(&channels);
+        }
+        channels::print(&channels);
+        return Ok(());
+    }
+    report.catalogue = cat.entries().count();
+    report.inaccessible = cat.inaccessible().to_vec();
+    for entry in cat.entries().filter(|entry| entry.json.is_upcoming()) {
+        if verbose && organize {
+            term::print(format_args!(
+                "{:?} hasn't premiered yet, leaving it out until its metadata is downloaded again",
+                entry.get_title()
+            ));
+        }
+        events.emit(Event::Skipped {
+            path: entry.path.first().map_or(Path::new(""), PathBuf::as_path),
+            reason: "pending premiere",
+        });
+        report.pending_premieres.push(PendingPremiere {
+            channel: entry.json.channel_name().to_string(),
+            title: entry.get_title().to_string(),
+            id: entry.json.id.clone(),
+            scheduled: entry.date.format("%Y-%m-%d %H:%M").to_string(),
+        });
+    }
+    if cli.require_media {
+        for entry in cat
+            .entries()
+            .filter(|entry| entry.get_media().next().is_none() && !entry.json.is_upcoming())
+        {
+            let info_json = entry.path.first().cloned().unwrap_or_default();
+            if verbose && organize {
+                term::print(format_args!(
+                    "No media file next to {info_json:?}, leaving the video out"
+                ));
+            }
+            events.emit(Event::Skipped {
+                path: &info_json,
+                reason: "no media",
+            });
+            report.missing_media.push(MissingMedia {
+                channel: entry.json.channel_name().to_string(),
+                title: entry.get_title().to_string(),
+                id: entry.json.id.clone(),
+                info_json,
+            });
         }
     }
+    let mut broken = HashSet::new();
+    for entry in cat.entries().filter(|entry| !entry.json.is_upcoming()) {
+        let Some((media, size)) = broken::find(entry, cli.min_media_size.0) else {
+            continue;
+        };
+        broken.insert(entry.json.id.as_str());
+        if verbose && organize {
+            term::say(
+                Color::Yellow,
+                "Broken",
+                format_args!("{media:?} has only {size} bytes, leaving the video out"),
+            );
+        }
+        events.emit(Event::Skipped {
+            path: media,
+            reason: "broken media",
+        });
+        report.broken_media.push(BrokenMedia {
+            channel: entry.json.channel_name().to_string(),
+            title: entry.get_title().to_string(),
+            id: entry.json.id.clone(),
+            media: media.to_path_buf(),
+            size,
+        });
+        if cli.quarantine_broken && organize && !cli.dry_run {
+            broken::quarantine(entry, &cli.source)?;
+            if verbose {
+                term::say(
+                    Color::Yellow,
+                    "Moving",
+                    format_args!("{media:?} to {}", broken::BROKEN_DIR),
+                );
+            }
+        }
+    }
+    // Every video goes to the target of the first route it matches, the rest to the main one.
+    let target_of = |entry: &CatalogueEntry| {
+        routes
+            .iter()
+            .find(|route| route.matches(entry))
+            .map_or(target.as_deref(), |route| Some(route.target.as_path()))
+    };
+    // Pairs of a target root and the target its videos are routed to, mirrors get the videos of
+    // the main target. Only organizing places anything into the mirrors.
+    let mut targets = vec![(target.clone(), target.clone())];
+    if organize
+        || matches!(
+            cli.command,
+            Some(Command::OrganizeOne { .. } | Command::Verify)
+        )
+    {
+        for mirror in cli.target.iter().skip(1) {
+            targets.push((Some(mirror.clone()), target.clone()));
+        }
+    }
+    for route in &routes {
+        if !targets
+            .iter()
+            .any(|(t, _)| t.as_ref() == Some(&route.target))
+        {
+            targets.push((Some(route.target.clone()), Some(route.target.clone())));
+        }
+    }
+
+    let mut libraries = Vec::new();
+    for (target, routed_to) in targets {
+        let target = target.as_deref().map(library_root);
+        let remote = target.as_deref().map(Remote::parse).transpose()?.flatten();
+        let target = match &remote {
+            Some(remote) => Some(remote.staging_dir()),
+            None => target,
+        };
+        let mut structure = cat.build_seasons(
+            SeasonOptions {
+                strategy: cli.seasons,
+                numbering: cli.numbering,
+                gaps: cli.season_gaps,
+            },
+            cli.sort,
+            &config.channels,
+            &cli.specials,
+            |entry| {
+                target_of(entry) == routed_to.as_deref()
+                    && (!cli.require_media || entry.get_media().next().is_some())
+                    && !entry.json.is_upcoming()
+                    && !broken.contains(entry.json.id.as_str())
+            },
+        );
+        if structure.is_empty() {
+            continue;
+        }
+        if let Some(name) = &cli.single_show {
+            structure = vec![SeasonedStructure::single_show(
+                name,
+                structure,
+                &config.channels,
+            )?];
+        }
+
+        let mut state = None;
+        if cli.stable_numbering || cli.skip_unchanged {
+            let target = target.as_ref().context(
+                "--stable-numbering and --skip-unchanged need a --target to keep the state in",
+            )?;
+            let mut loaded = State::load(target)?;
+
+            if cli.stable_numbering {
+                for channel in &mut structure {
+                    loaded.apply_numbering(channel);
+                }
+            }
+
+            state = Some(loaded);
+        }
+
+        libraries.push(Library {
+            target,
+            remote,
+            structure,
+            state,
+        });
+    }
+
+    if let Some(Command::Verify) = cli.command {
+        return verify::run(&cat, &libraries);
+    }
+
+    if let Some(Command::Snapshot { output }) = &cli.command {
+        let snapshot = Snapshot::take(&cli.source, &cat, &libraries);
+        write_json(output, &snapshot)?;
+        if verbose {
+            term::print(format_args!(
+                "Saved {} videos to {output:?}, hash {}",
+                snapshot.videos.len(),
+                snapshot.hash
+            ));
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Diff { against }) = &cli.command {
+        let old = Snapshot::load(against)?;
+        Snapshot::take(&cli.source, &cat, &libraries).diff(&old);
+        return Ok(());
+    }
+
+    if let Some(Command::Stats) = cli.command {
+        let structure = libraries
+            .into_iter()
+            .flat_map(|library| library.structure)
+            .collect::<Vec<_>>();
+        return stats::print(&stats::collect(&structure), cli.format.unwrap_or_default());
+    }
+
+    if let Some(Command::List { channel }) = &cli.command {
+        let structure = libraries
+            .into_iter()
+            .flat_map(|library| library.structure)
+            .filter(|c| channel.is_empty() || channel.contains(&c.channel_name))
+            .collect::<Vec<_>>();
+        return list::print(&structure, cli.format.unwrap_or_default());
+    }
+
+    if let Some(Command::OrganizeOne { path }) = &cli.command {
+        if target.is_none() && routes.is_empty() {
+            bail!("organize-one needs a --target (or \"target\" in the config) to organize into");
+        }
+
+        let info_json = info_json_for(path);
+        let Some(video) = CatalogueEntry::new(
+            &info_json,
+            &cli.source,
+            &catalogue_options,
+            &mut SiblingIndex::default(),
+        )
+        .with_context(|| format!("Can't load {info_json:?}"))?
+        else {
+            return Ok(());
+        };
+
+        for library in &mut libraries {
+            library
+                .structure
+                .retain_mut(|channel| channel.retain_videos(|e| e.json.id == video.json.id));
+        }
+        libraries.retain(|library| !library.structure.is_empty());
+        if libraries.is_empty() {
+            bail!("{info_json:?} isn't part of the catalogue built from the source directory");
+        }
+    }
+
+    let options = BuilderOptions {
+        dry_run: cli.dry_run || planning,
+        verbose: verbose && !draw_tree,
+        events: events.clone(),
+        reconcile: cli.reconcile,
+        season_artwork: cli.season_artwork,
+        season_artwork_name: cli.season_artwork_name,
+        artwork: cli.artwork,
+        link_mode: cli.link_mode,
+        naming: Naming {
+            rules: config.title_rules.clone(),
+            channel_rules: config
+                .channels
+                .iter()
+                .map(|(name, channel)| (name.clone(), channel.title_rules.clone()))
+                .collect(),
+            channel_prefix: cli.single_show.is_some(),
+            strip_emoji: cli.strip_emoji,
+            strip_channel_prefix: cli.strip_channel_prefix,
+            strip_hashtags: cli.strip_hashtags,
+            title_case: cli.title_case,
+            ascii_only: cli.ascii_only,
+            suffix_id: cli.suffix_id,
+            normalization: cli
+                .unicode_normalization
+                .or(config.unicode_normalization)
+                .unwrap_or_default(),
+        },
+        conflicts: Conflicts::new(cli.on_conflict, cli.prompt),
+        nfo: cli.nfo,
+        link_metadata_sidecars: cli.link_metadata_sidecars,
+        verify_copies: cli.verify_copies,
+        limits: match cli.yes_really {
+            true => Limits::default(),
+            false => Limits {
+                max_operations: cli.max_operations.or(config.max_operations),
+                max_delete: cli.max_delete.or(config.max_delete),
+            },
+        },
+        link_prefix_map: cli.link_prefix_map.clone(),
+        layout: match (cli.layout, &config.layout) {
+            (Some(layout), _) => layout,
+            (None, Some(layout)) => layout
+                .parse()
+                .with_context(|| format!("Invalid layout {layout:?} in the config"))?,
+            (None, None) => Layout::default(),
+        },
+        music_video: false,
+        tag_subtitle_language: cli.tag_subtitle_language,
+        limit_output: cli.limit_output,
+        retries: cli.retries,
+        sort: cli.sort,
+        plan: if planning || draw_tree {
+            Recorder::enabled()
+        } else {
+            Recorder::default()
+        },
+    };
+
+    if let Some(Command::Explain { video }) = &cli.command {
+        return explain::print(
+            video,
+            &cat,
+            &libraries,
+            &options,
+            config,
+            &catalogue_options,
+        );
+    }
+
+    for (flag, given) in [
+        ("--transfer-watched", cli.transfer_watched),
+        ("--scan-media-server", cli.scan_media_server),
+    ] {
+        if given && config.media_server.is_none() {
+            bail!("{flag} needs a \"media_server\" in the config");
+        }
+    }
+    if options.verify_copies && options.limits.is_set() && !options.dry_run {
+        // Limited runs are planned and then applied, which copies without hashing.
+        bail!("--verify-copies can't be combined with --max-operations or --max-delete");
+    }
+    let watched_server = config
+        .media_server
+        .as_ref()
+        .filter(|_| cli.transfer_watched);
+    let scan_server = config
+        .media_server
+        .as_ref()
+        .filter(|_| cli.scan_media_server);
+    if let Some(Command::Migrate) = cli.command {
+        migrate::run(&libraries, &options, config, watched_server, verbose)?;
+    }
+
+    let duplicates = match cli.dedupe {
+        Some(dedupe) => {
+            let index = DuplicateIndex::build(&cat, catalogue_options.verbose)?;
+            if dedupe == Dedupe::Report {
+                index.report();
+                None
+            } else {
+                Some(index)
+            }
+        }
+        None => None,
+    };
+
+    if cli.trash.is_some() {
+        trash::empty(&cli.source, Some(cli.trash_days), options.dry_run, verbose)?;
+    }
+
+    let mut placed = HashSet::new();
+    let mut feed_items = Vec::new();
+    // Runs with other options or another config may organize the same channel differently.
+    let mut options_salt = std::env::args_os()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .join("\0")
+        .into_bytes();
+    options_salt.push(0);
+    options_salt.extend(&config.contents);
+    let mirrored = libraries.len() > 1;
+    // Every target root gets boxsets listing its own files.
+    let collection_dirs = libraries.len() > 1;
+    for library in libraries {
+        if signals::interrupted() {
+            break;
+        }
+
+        let Library {
+            target,
+            remote,
+            mut structure,
+            mut state,
+        } = library;
+        let Some(target) = target else {
+            if cli.dedupe == Some(Dedupe::Report) {
+                // Only duplicates were asked for.
+                continue;
+            }
+            bail!("Videos not matching any --route need a --target (or \"target\" in the config)");
+        };
+
+        // Changes to a remote target are planned in its staging directory, then uploaded. Local
+        // changes are planned first when they may have to be stopped for going over the limits.
+        let guarded = remote.is_none() && !options.dry_run && options.limits.is_set();
+        let options = match &remote {
+            Some(_) if planning => bail!("ssh:// targets can't be planned"),
+            Some(_) => BuilderOptions {
+                dry_run: true,
+                link_mode: LinkMode::Symlink,
+                plan: Recorder::enabled(),
+                ..options.clone()
+            },
+            None if guarded => BuilderOptions {
+                dry_run: true,
+                plan: Recorder::enabled(),
+                ..options.clone()
+            },
+            None => options.clone(),
+        };
+        let name = remote.as_ref().map_or(&target, |remote| &remote.url);
+
+        let mut manifest = Manifest::load(&target)?;
+        options.plan.start(&target);
+        if let Some(server) = watched_server
+            && (!options.dry_run || guarded)
+            && let Err(err) = watched::apply(server, &target, verbose)
+        {
+            // The library itself can still be organized.
+            let message = format!("Can't carry over watch state: {err:#}");
+            term::error(Color::Red, message);
+        }
+        if cli.trash.is_some() && remote.is_none() {
+            let dry_run = options.dry_run && !guarded;
+            trash::empty(&target, Some(cli.trash_days), dry_run, verbose)?;
+        }
+
+        if let Some(max) = cli.max_library_size {
+            let pruned = retention::select(&mut structure, max.0);
+            report.pruned.extend(retention::prune(
+                &pruned,
+                &target,
+                &mut manifest,
+                cli.prune_sources.then_some(cli.source.as_path()),
+                options.dry_run,
+                options.verbose,
+                &options.plan,
+            )?);
+        }
+
+        let playlists = cli
+            .playlists
+            .then(|| structure.iter().map(ChannelPlaylist::of).collect_vec());
+
+        let mut unchanged = 0;
+        let channels = structure
+            .into_iter()
+            .filter_map(|chan| {
+                let hash = cli
+                    .skip_unchanged
+                    .then(|| state::channel_hash(&chan, &options_salt));
+                if let (Some(state), Some(hash)) = (&state, &hash)
+                    && state.is_unchanged(&chan.channel_name, hash)
+                {
+                    unchanged += 1;
+                    return None;
+                }
+                Some((chan, hash))
+            })
+            .collect_vec();
+
+        // Planned runs are checked when the plan is applied.
+        if options.link_mode == LinkMode::Copy && !options.dry_run {
+            // Files placed in earlier runs aren't copied again.
+            let placed = manifest
+                .links
+                .values()
+                .map(|known| &known.source)
+                .collect::<HashSet<_>>();
+            let needed = channels
+                .iter()
+                .flat_map(|(channel, _)| &channel.seasons)
+                .flat_map(|season| &season.videos)
+                .flat_map(|ep| &ep.entry.path)
+                .filter(|file| !placed.contains(file))
+                .filter_map(|file| file.metadata().ok())
+                .map(|meta| meta.len())
+                .sum();
+            space::check(&target, needed, cli.force)?;
+        }
+
+        let mut library_total = Summary::default();
+        for (chan, hash) in channels {
+            if signals::interrupted() {
+                break;
+            }
+
+            let channel_name = chan.channel_name.clone();
+            let options = channel_options(&options, config, &channel_name)?;
+            let builder = DirectoryBuilder::new(&target, chan, &options);
+            let builder = match &duplicates {
+                Some(duplicates) => builder.with_duplicates(duplicates),
+                None => builder,
+            };
+
+            let summary = builder.build(&mut manifest)?;
+            library_total += &summary;
+            report.add_channel(&channel_name, summary);
+            // A channel stopped halfway must be organized again.
+            if let (Some(state), Some(hash)) = (&mut state, hash)
+                && !signals::interrupted()
+            {
+                state.set_hash(&channel_name, hash);
+            }
+        }
+        if verbose && unchanged > 0 {
+            term::print(format_args!("{unchanged} unchanged channels skipped"));
+        }
+
+        if verbose && mirrored {
+            term::print(format_args!(
+                "{name:?}: {} files placed, {} directories, {} artwork, {} NFOs",
+                library_total.links,
+                library_total.directories,
+                library_total.artwork,
+                library_total.nfo
+            ));
+        }
+        let changed = library_total.has_changes();
+        report.targets.push(TargetReport {
+            target: name.clone(),
+            summary: library_total,
+        });
+
+        if let Some(dir) = &cli.collections {
+            // Boxsets kept inside the library must not show up as a show of their own.
+            let inside = dir.strip_prefix(&target).is_ok_and(|rest| {
+                rest.components().next().is_some()
+                    && rest.components().all(|c| matches!(c, Component::Normal(_)))
+            });
+            if inside {
+                hide_from_library(dir, options.dry_run, verbose, &options.plan)?;
+            }
+            let dir = match collection_dirs {
+                true => dir.join(collection::dir_name(name)),
+                false => dir.clone(),
+            };
+            report.collections += collection::write(&cat, &target, &manifest, &dir, &options)?;
+        }
+
+        if let Some(playlists) = &playlists {
+            report.playlists += playlist::write(playlists, &target, &manifest, &options)?;
+        }
+
+        if cli.feed.is_some() {
+            feed::collect(&cat, &target, name, &manifest, &mut feed_items);
+        }
+
+        placed.extend(manifest.links.values().map(|known| known.id.clone()));
+
+        if let Some(remote) = &remote {
+            options.plan.finish(manifest, state);
+            let plan = options.plan.take();
+            if !cli.dry_run {
+                for library in &plan.libraries {
+                    options.limits.check(library)?;
+                    remote.upload(library, verbose)?;
+                }
+                plan.apply(false, true, false)?;
+            } else if draw_tree {
+                let trees = plan
+                    .libraries
+                    .iter()
+                    .map(|library| tree::of_plan(library, &remote.url));
+                tree::print(&trees.collect_vec());
+            }
+        } else if guarded {
+            options.plan.finish(manifest, state);
+            let plan = options.plan.take();
+            for library in &plan.libraries {
+                options.limits.check(library)?;
+            }
+            plan.apply(false, cli.force, false)?;
+        } else if options.plan.is_enabled() {
+            options.plan.finish(manifest, state);
+            if draw_tree {
+                options
+                    .plan
+                    .current(|library| tree::print(&[tree::of_plan(library, &library.target)]));
+            }
+        } else if !options.dry_run {
+            manifest.save(&target)?;
+
+            if let Some(state) = &state {
+                state.save(&target)?;
+            }
+        }
+
+        let written = !cli.dry_run && (remote.is_some() || guarded || !options.plan.is_enabled());
+        if let Some(server) = scan_server
+            && changed
+            && written
+            && let Err(err) = server.scan(&target, verbose)
+        {
+            // The library is organized, the server just picks it up on its own schedule.
+            let message = format!("Can't ask the media server to scan {name:?}: {err:#}");
+            term::error(Color::Red, message);
+        }
+    }
+
+    if let Some(path) = &cli.feed {
+        report.feed = feed::write(path, feed_items, &options)?;
+    }
+
+    if let Some(Command::Plan { output }) = &cli.command {
+        let operations = options.plan.save(output)?;
+        if verbose {
+            term::print(format_args!("Wrote {operations} operations to {output:?}"));
+        }
+    }
+
+    report.pending = cat
+        .entries()
+        .filter(|entry| !placed.contains(&entry.json.id))
+        .count();
+    report.interrupted = signals::interrupted();
 
     Ok(())
 }
@@ -0,0 +1,83 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OrganizerError;
+
+pub const MANIFEST_FILE_NAME: &str = ".ytdlp-organise.json";
+
+/// Provenance index stored in the target root. Maps every link created by the tool (relative to
+/// the target root) back to the source file and the video it belongs to.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest {
+    pub links: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    #[serde(with = "crate::os_path")]
+    pub source: PathBuf,
+    pub id: String,
+    /// SHA-256 of a copy checked against its source, with `--verify-copies`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl Manifest {
+    pub fn path(target: &Path) -> PathBuf {
+        target.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Loads the manifest from the target root, a missing file yields an empty manifest.
+    pub fn load(target: &Path) -> anyhow::Result<Self> {
+        let path = Manifest::path(target);
+        let invalid = |reason: String| OrganizerError::StateError {
+            path: path.clone(),
+            reason,
+        };
+
+        match File::open(&path) {
+            Ok(file) => Ok(serde_json::from_reader(std::io::BufReader::new(file))
+                .map_err(|err| invalid(err.to_string()))?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(err) => Err(invalid(err.to_string()))?,
+        }
+    }
+
+    /// Writes the manifest next to the previous one and renames it into place, so an interrupted
+    /// run never leaves a truncated file behind. Daily backups of the previous one are kept (see
+    /// `backup::rotate_daily`).
+    pub fn save(&self, target: &Path) -> anyhow::Result<()> {
+        let path = Manifest::path(target);
+        crate::backup::rotate_daily(&path)?;
+        crate::write_json(&path, self)
+    }
+
+    /// Records a placed file. A hash verified in an earlier run is kept while the link still
+    /// points at the same source; `sha256` replaces it.
+    pub fn record(&mut self, link: PathBuf, source: PathBuf, id: &str, sha256: Option<String>) {
+        let sha256 = sha256.or_else(|| {
+            self.links
+                .get(&link)
+                .filter(|known| known.source == source)
+                .and_then(|known| known.sha256.clone())
+        });
+        self.links.insert(
+            link,
+            ManifestEntry {
+                source,
+                id: id.to_string(),
+                sha256,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, link: &Path) {
+        self.links.remove(link);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    prefix::{self, PrefixMap},
+    term,
+};
+
+/// An Emby or Jellyfin server, `"media_server"` in the config.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MediaServer {
+    /// e.g. `http://localhost:8096`
+    pub url: String,
+    pub api_key: String,
+    /// Where the server sees the target, as `FROM=TO` like `--link-prefix-map`
+    #[serde(default)]
+    pub path_map: Vec<PrefixMap>,
+    /// The library `--scan-media-server` scans, the whole server when not set
+    pub library: Option<LibraryConfig>,
+}
+
+/// A library (virtual folder) of the server, found by its id, its name or a folder of it being
+/// the target.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct LibraryConfig {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    /// Create the library with the target as its folder when the server has none of that name
+    #[serde(default)]
+    pub create: bool,
+    /// Content type of a created library
+    #[serde(default = "default_collection_type")]
+    pub collection_type: String,
+}
+
+fn default_collection_type() -> String {
+    "tvshows".to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct UserData {
+    #[serde(default)]
+    pub played: bool,
+    #[serde(default)]
+    pub play_count: u64,
+    #[serde(default)]
+    pub playback_position_ticks: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct User {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Items {
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Item {
+    pub id: String,
+    path: Option<PathBuf>,
+    #[serde(default)]
+    pub user_data: UserData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct VirtualFolder {
+    name: String,
+    item_id: Option<String>,
+    #[serde(default)]
+    locations: Vec<PathBuf>,
+}
+
+impl MediaServer {
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let url = format!("{}{path}", self.url.trim_end_matches('/'));
+        let data = ureq::get(&url)
+            .header("X-Emby-Token", &self.api_key)
+            .call()
+            .with_context(|| format!("Media server request {url} failed"))?
+            .into_body()
+            .read_to_vec()?;
+
+        serde_json::from_slice(&data).with_context(|| format!("Unexpected answer from {url}"))
+    }
+
+    pub fn post(&self, path: &str, body: serde_json::Value) -> anyhow::Result<()> {
+        let url = format!("{}{path}", self.url.trim_end_matches('/'));
+        ureq::post(&url)
+            .header("X-Emby-Token", &self.api_key)
+            .header("Content-Type", "application/json")
+            .send(body.to_string())
+            .with_context(|| format!("Media server request {url} failed"))?;
+
+        Ok(())
+    }
+
+    pub fn users(&self) -> anyhow::Result<Vec<String>> {
+        let users: Vec<User> = self.get("/Users")?;
+        Ok(users.into_iter().map(|user| user.id).collect())
+    }
+
+    /// The videos a user can see, by the path the server knows them under.
+    pub fn items(&self, user: &str) -> anyhow::Result<HashMap<PathBuf, Item>> {
+        let items: Items = self.get(&format!(
+            "/Users/{user}/Items?Recursive=true&IncludeItemTypes=Episode,MusicVideo,Video\
+             &Fields=Path"
+        ))?;
+
+        Ok(items
+            .items
+            .into_iter()
+            .filter_map(|item| Some((item.path.clone()?, item)))
+            .collect())
+    }
+
+    /// `path` of the target as the server sees it.
+    pub fn server_path(&self, path: &Path) -> PathBuf {
+        prefix::link_target(&self.path_map, path).into_owned()
+    }
+
+    /// Asks the server to scan the configured library, or every library when none is
+    /// configured. A library to be created is created with `target` as its folder, which also
+    /// scans it.
+    pub fn scan(&self, target: &Path, verbose: bool) -> anyhow::Result<()> {
+        let Some(library) = &self.library else {
+            if verbose {
+                term::print("Asking the media server to scan every library");
+            }
+            return self.post("/Library/Refresh", json!({}));
+        };
+
+        let id = match &library.id {
+            Some(id) => Some(id.clone()),
+            None => self.find_library(library, target)?,
+        };
+        if let Some(id) = id {
+            if verbose {
+                term::print(format_args!("Asking the media server to scan library {id}"));
+            }
+            return self.post(&format!("/Items/{id}/Refresh?Recursive=true"), json!({}));
+        }
+
+        let Some(name) = library.name.as_deref().filter(|_| library.create) else {
+            bail!(
+                "The media server has no library for {target:?}; name it in \"library\" with \
+                 \"create\": true to have it created"
+            );
+        };
+        if verbose {
+            term::print(format_args!("Creating media server library {name:?}"));
+        }
+        let folder = self.server_path(target);
+        let query = [
+            ("name", name),
+            ("collectionType", library.collection_type.as_str()),
+            ("paths", &folder.to_string_lossy()),
+            ("refreshLibrary", "true"),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{key}={}", encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+        self.post(&format!("/Library/VirtualFolders?{query}"), json!({}))
+    }
+
+    /// Id of the library named in the config, or else the one holding the target as a folder.
+    fn find_library(
+        &self,
+        library: &LibraryConfig,
+        target: &Path,
+    ) -> anyhow::Result<Option<String>> {
+        let folders: Vec<VirtualFolder> = self.get("/Library/VirtualFolders")?;
+        let folder = self.server_path(target);
+
+        Ok(folders
+            .into_iter()
+            .find(|found| match &library.name {
+                Some(name) => found.name == *name,
+                None => found.locations.contains(&folder),
+            })
+            .and_then(|found| found.item_id))
+    }
+}
+
+/// Percent-encodes a query parameter value.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,63 @@
+use std::{fmt::Write as _, path::Path, time::Duration};
+
+use crate::summary::Report;
+
+/// Writes the run report in the Prometheus text format for node_exporter's textfile collector.
+/// The file is replaced atomically so the collector never reads a partial file.
+pub fn write(path: &Path, report: &Report, duration: Duration) -> anyhow::Result<()> {
+    let mut out = String::new();
+
+    let mut gauge = |name: &str, help: &str, value: f64| {
+        let _ = writeln!(out, "# HELP ytdlp_organise_{name} {help}");
+        let _ = writeln!(out, "# TYPE ytdlp_organise_{name} gauge");
+        let _ = writeln!(out, "ytdlp_organise_{name} {value}");
+    };
+
+    gauge(
+        "last_run_timestamp_seconds",
+        "Unix time the last run finished.",
+        chrono::Utc::now().timestamp() as f64,
+    );
+    gauge(
+        "last_run_success",
+        "Whether the last run finished without errors.",
+        if report.errors.is_empty() { 1.0 } else { 0.0 },
+    );
+    gauge(
+        "run_duration_seconds",
+        "Duration of the last run.",
+        duration.as_secs_f64(),
+    );
+    gauge(
+        "catalogue_videos",
+        "Videos found in the source.",
+        report.catalogue as f64,
+    );
+    gauge(
+        "new_episodes",
+        "Episodes organized by the last run.",
+        report.new_episodes as f64,
+    );
+    gauge(
+        "links_created",
+        "Files placed in the target by the last run.",
+        report.total.links as f64,
+    );
+    gauge(
+        "errors",
+        "Errors in the last run.",
+        report.errors.len() as f64,
+    );
+    gauge(
+        "inaccessible_paths",
+        "Paths of the source the last run couldn't read.",
+        report.inaccessible.len() as f64,
+    );
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, out)?;
+    std::fs::rename(&tmp, path)?;
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::{
+    BuilderOptions, DirectoryBuilder, Library, channel_options,
+    config::Config,
+    manifest::Manifest,
+    media_server::MediaServer,
+    reconcile::TargetIndex,
+    term::{self, Color},
+    watched,
+};
+
+/// Moves the episode files of a target organized with other naming, numbering or layout options
+/// (or an older version) to where organizing places them now. Files are found through the
+/// manifest, the link they hold or a `[video id]` in their name, and renamed rather than placed
+/// again, so copies aren't copied twice and hard links stay linked. With a `server` the watch
+/// state of the moved episodes is kept to carry it over once the server has scanned them.
+pub fn run(
+    libraries: &[Library],
+    options: &BuilderOptions,
+    config: &Config,
+    server: Option<&MediaServer>,
+    verbose: bool,
+) -> anyhow::Result<()> {
+    for library in libraries {
+        let Some(target) = &library.target else {
+            continue;
+        };
+        let mut manifest = Manifest::load(target)?;
+        let index = TargetIndex::build(target, target, &manifest);
+
+        let mut moves = Vec::new();
+        let mut media = Vec::new();
+        for channel in &library.structure {
+            let options = channel_options(options, config, &channel.channel_name)?;
+            let builder = DirectoryBuilder::new(target, channel.clone(), &options);
+
+            for season in &builder.channel.seasons {
+                for ep in &season.videos {
+                    let id = &ep.entry.json.id;
+                    for (source, wanted) in builder.planned_files(season, ep, &manifest) {
+                        let existing = match source {
+                            Some(source) => index.find(source, id),
+                            None if wanted.extension().is_some_and(|ext| ext == "nfo") => {
+                                index.find_nfo(ep.entry.info_json().map(PathBuf::as_path), id)
+                            }
+                            None => None,
+                        };
+                        let Some(existing) = existing else {
+                            continue;
+                        };
+                        if *existing == wanted || wanted.symlink_metadata().is_ok() {
+                            continue;
+                        }
+
+                        if verbose {
+                            term::say(
+                                Color::Green,
+                                "Moving",
+                                format_args!("{} -> {}", existing.display(), wanted.display()),
+                            );
+                        }
+                        if source.is_some_and(|source| ep.entry.get_media().any(|m| m == source)) {
+                            media.push((existing.clone(), wanted.clone()));
+                        }
+                        moves.push((existing.clone(), wanted));
+                    }
+                }
+            }
+        }
+
+        if !options.dry_run {
+            options.limits.check_moves(target, moves.len())?;
+            if let Some(server) = server {
+                watched::capture(server, &media, target, verbose)?;
+            }
+            for (from, to) in &moves {
+                move_file(from, to, target, &mut manifest)?;
+            }
+            for dir in moves.iter().filter_map(|(from, _)| from.parent()) {
+                remove_empty(dir, target);
+            }
+            manifest.save(target)?;
+        }
+        if verbose {
+            term::print(format_args!(
+                "{target:?}: {} files moved to the current layout",
+                moves.len()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn move_file(from: &Path, to: &Path, root: &Path, manifest: &mut Manifest) -> anyhow::Result<()> {
+    if let Some(dir) = to.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Can't create {dir:?}"))?;
+    }
+    std::fs::rename(from, to).with_context(|| format!("Can't move {from:?} to {to:?}"))?;
+
+    // The manifest entry moves along, keeping a hash checked by `--verify-copies`.
+    if let (Ok(old), Ok(new)) = (from.strip_prefix(root), to.strip_prefix(root))
+        && let Some(known) = manifest.links.remove(old)
+    {
+        manifest.links.insert(new.to_path_buf(), known);
+    }
+
+    Ok(())
+}
+
+/// Removes `dir` and its parents up to `root` for as long as they are empty.
+fn remove_empty(dir: &Path, root: &Path) {
+    for dir in dir
+        .ancestors()
+        .take_while(|dir| *dir != root && dir.starts_with(root))
+    {
+        if std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
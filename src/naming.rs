@@ -0,0 +1,309 @@
+use std::{borrow::Cow, collections::HashMap, sync::LazyLock};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use unicode_normalization::{IsNormalized, UnicodeNormalization};
+
+use crate::CatalogueEntry;
+
+/// A regex replacement applied to video titles, e.g. `{"pattern": "^EP\\. \\d+ \\| ", "replace": ""}`.
+/// The replacement may refer to capture groups as `$1` or `${name}`.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TitleRule {
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    #[serde(default)]
+    pub replace: String,
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// Emoji (including skin tone modifiers, flags and variation selectors) plus control and
+/// invisible formatting characters such as zero-width spaces and joiners.
+static EMOJI: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"[\p{Extended_Pictographic}\p{Emoji_Modifier}\p{Regional_Indicator}\u{20E3}\u{FE0E}\u{FE0F}\p{Cc}\p{Cf}]",
+    )
+    .unwrap()
+});
+
+/// Episode numbers channels put in front of titles: `#12`, `Ep. 3`, `Episode 4:`, `Part 2 -`,
+/// `E05`, `12.` or `12 -`. Bare numbers have at most 3 digits, `1984 - Documentary` starts with a
+/// year.
+static EPISODE_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?:#|ep\.?|episode|part|pt\.?)\s*\d+\s*[-–—:.|)]?|e\d+\s*[-–—:.|)]?|\d{1,3}\s*[-–—:.|)])\s+",
+    )
+    .unwrap()
+});
+
+/// Title media servers sort by: without emoji, a leading episode number or a leading "The".
+pub fn sort_title(title: &str) -> String {
+    let mut sort = EMOJI.replace_all(title, "").split_whitespace().join(" ");
+    sort = EPISODE_PREFIX.replace(&sort, "").into_owned();
+    if let Some(rest) = sort
+        .strip_prefix("The ")
+        .or_else(|| sort.strip_prefix("the "))
+    {
+        sort = rest.trim_start().to_string();
+    }
+
+    if sort.is_empty() {
+        title.to_string()
+    } else {
+        sort
+    }
+}
+
+/// Hashtags at the end of a title (`... #shorts #viral`). A tag needs a letter, so `Part #2`
+/// stays.
+static TRAILING_HASHTAGS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:\s+#[\p{L}\p{N}_]*\p{L}[\p{L}\p{N}_]*)+\s*$").unwrap());
+
+/// Words kept lowercase by `title_case` unless they start the title.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "the", "to",
+    "vs", "with",
+];
+
+/// Acronyms `title_case` leaves in capitals. `US` isn't one of them, shouted it's mostly "us".
+const ACRONYMS: &[&str] = &[
+    "AI", "ASMR", "BBC", "CEO", "CPU", "DIY", "DJ", "DNA", "EU", "FAQ", "FBI", "FPS", "GPU", "GTA",
+    "HD", "HDR", "LED", "NASA", "NBA", "NFL", "NYC", "PC", "RPG", "SUV", "TV", "UFO", "UK", "USA",
+    "USB", "VR", "WWII",
+];
+
+/// `text` in Title Case when most of its words are shouted in ALL CAPS: those words keep their
+/// first letter and lowercase the rest, except for `MINOR_WORDS` after the first and `ACRONYMS`.
+/// `None` for text that isn't shouting, so a single acronym or `NASA in the 60s` stays as
+/// written.
+fn title_case(text: &str) -> Option<String> {
+    let is_shouting = |word: &str| {
+        word.chars().filter(|c| c.is_uppercase()).count() >= 2
+            && !word.chars().any(char::is_lowercase)
+    };
+    let cased_words = text
+        .split_whitespace()
+        .filter(|word| word.chars().filter(|c| c.is_alphabetic()).count() >= 2)
+        .collect_vec();
+    let shouting = cased_words.iter().filter(|word| is_shouting(word)).count();
+    if shouting < 2 || shouting * 2 <= cased_words.len() {
+        return None;
+    }
+
+    let mut first = true;
+    let cased = text
+        .split(' ')
+        .map(|word| {
+            let is_first = first && !word.is_empty();
+            first &= word.is_empty();
+            if !is_shouting(word) {
+                return word.to_string();
+            }
+            let lower = word.to_lowercase();
+            if !is_first && MINOR_WORDS.contains(&lower.as_str()) {
+                return lower;
+            }
+            if ACRONYMS.contains(&word.trim_matches(|c: char| !c.is_alphanumeric())) {
+                return word.to_string();
+            }
+            // Capitalizes the first letter, after any leading quotes or brackets.
+            match lower.find(char::is_alphanumeric) {
+                Some(start) => {
+                    let (before, rest) = lower.split_at(start);
+                    let mut chars = rest.chars();
+                    let initial = chars.next().into_iter().flat_map(char::to_uppercase);
+                    before.chars().chain(initial).chain(chars).collect()
+                }
+                None => lower,
+            }
+        })
+        .join(" ");
+
+    Some(cased)
+}
+
+/// What channels put between their name and the title.
+const SEPARATORS: &str = ":|-–—·•/])>";
+
+/// The title without a leading channel name and the separator after it (`Channel: `,
+/// `CHANNEL | `, `[Channel] `). Letters and digits are compared ignoring case, anything else in
+/// either name is skipped, so `Tech-Talks` matches `Tech Talks`. `None` when the title doesn't
+/// start with the channel name as a whole word or nothing would be left.
+fn strip_channel_name<'t>(title: &'t str, channel: &str) -> Option<&'t str> {
+    let mut wanted = channel
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    wanted.peek()?;
+
+    let mut rest = title;
+    while wanted.peek().is_some() {
+        let c = rest.chars().next()?;
+        rest = &rest[c.len_utf8()..];
+        if !c.is_alphanumeric() {
+            continue;
+        }
+        for lower in c.to_lowercase() {
+            if wanted.next() != Some(lower) {
+                return None;
+            }
+        }
+    }
+
+    // Neither `ChannelXtra` nor `ChannelX's review` start with the name of `ChannelX`.
+    let is_separator = |c: char| c.is_whitespace() || SEPARATORS.contains(c);
+    if !rest.starts_with(is_separator) {
+        return None;
+    }
+    let rest = rest.trim_start_matches(is_separator);
+
+    (!rest.is_empty()).then_some(rest)
+}
+
+/// Unicode form file and directory names are written in. Filesystems and file sharing protocols
+/// disagree on it (macOS stores decomposed names, Linux keeps what it's given), so without one the
+/// same title can end up as two different names on two machines.
+#[derive(ValueEnum, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    /// Composed characters (`é` as one code point), what most tools produce
+    #[default]
+    Nfc,
+    /// Decomposed characters (`e` followed by a combining accent)
+    Nfd,
+    /// Names are written as the titles spell them
+    None,
+}
+
+impl Normalization {
+    pub fn apply(self, name: &str) -> Cow<'_, str> {
+        match self {
+            Normalization::Nfc
+                if unicode_normalization::is_nfc_quick(name.chars()) != IsNormalized::Yes =>
+            {
+                Cow::Owned(name.nfc().collect())
+            }
+            Normalization::Nfd
+                if unicode_normalization::is_nfd_quick(name.chars()) != IsNormalized::Yes =>
+            {
+                Cow::Owned(name.nfd().collect())
+            }
+            _ => Cow::Borrowed(name),
+        }
+    }
+}
+
+/// Turns video titles into episode titles and file names.
+#[derive(Clone, Default)]
+pub struct Naming {
+    pub rules: Vec<TitleRule>,
+    /// Rules applied after `rules` to the videos of a single channel
+    pub channel_rules: HashMap<String, Vec<TitleRule>>,
+    /// Prefix titles with the channel name (`Channel - Title`)
+    pub channel_prefix: bool,
+    /// Remove a leading channel name from titles, see `strip_channel_name`
+    pub strip_channel_prefix: bool,
+    /// Remove hashtags from the end of titles
+    pub strip_hashtags: bool,
+    /// Rewrite titles and show names shouted in ALL CAPS in Title Case, see `title_case`
+    pub title_case: bool,
+    /// Drop emoji and control characters from file names
+    pub strip_emoji: bool,
+    /// Transliterate file names to plain ASCII, implies `strip_emoji`
+    pub ascii_only: bool,
+    /// Append ` [<video id>]` to file names
+    pub suffix_id: bool,
+    pub normalization: Normalization,
+}
+
+impl Naming {
+    /// Title of the episode after all title rules were applied in order.
+    pub fn title(&self, entry: &CatalogueEntry) -> String {
+        let channel = entry.json.channel_name();
+        let mut title = self.apply_rules(entry, channel);
+        if self.strip_channel_prefix
+            && let Some(rest) = strip_channel_name(&title, channel)
+        {
+            title = rest.to_string();
+        }
+        if self.strip_hashtags {
+            let stripped = TRAILING_HASHTAGS.replace(&title, "");
+            // A title made only of hashtags is kept as it is.
+            if !stripped.trim().is_empty() && stripped.len() < title.len() {
+                title = stripped.into_owned();
+            }
+        }
+
+        if self.title_case
+            && let Some(cased) = title_case(&title)
+        {
+            title = cased;
+        }
+
+        if self.channel_prefix {
+            format!("{} - {title}", self.show(channel))
+        } else {
+            title
+        }
+    }
+
+    /// Name of the show a channel is organized as.
+    pub fn show<'c>(&self, channel: &'c str) -> Cow<'c, str> {
+        match self.title_case.then(|| title_case(channel)).flatten() {
+            Some(cased) => Cow::Owned(cased),
+            None => Cow::Borrowed(channel),
+        }
+    }
+
+    fn apply_rules(&self, entry: &CatalogueEntry, channel: &str) -> String {
+        let mut title = Cow::Borrowed(entry.get_title());
+        let channel_rules = self.channel_rules.get(channel).into_iter().flatten();
+
+        for rule in self.rules.iter().chain(channel_rules) {
+            // Only rules that matched allocate a new title.
+            let replaced = match rule.pattern.replace_all(&title, rule.replace.as_str()) {
+                Cow::Owned(replaced) => Some(replaced),
+                Cow::Borrowed(_) => None,
+            };
+            if let Some(replaced) = replaced {
+                title = Cow::Owned(replaced);
+            }
+        }
+
+        let trimmed = title.trim();
+        if trimmed.is_empty() {
+            // A rule that eats the whole title would leave nothing to name the files after.
+            entry.get_title().to_string()
+        } else {
+            trimmed.to_string()
+        }
+    }
+
+    /// File name (without extension) the episode files are placed under.
+    pub fn file_stem(&self, entry: &CatalogueEntry) -> String {
+        let mut stem = self.title(entry);
+
+        if self.strip_emoji || self.ascii_only {
+            stem = EMOJI.replace_all(&stem, "").split_whitespace().join(" ");
+        }
+        if self.ascii_only {
+            stem = deunicode::deunicode_with_tofu(&stem, "");
+        }
+        if stem.trim().is_empty() {
+            // Titles made only of emoji still need a name, fall back to the video id.
+            stem = entry.json.id.clone();
+        } else if self.suffix_id {
+            stem = format!("{stem} [{}]", entry.json.id);
+        }
+
+        stem.replace("/", "_")
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use crate::{CatalogueEntry, naming::sort_title};
+
+/// A person credited for a video, listed under People by Emby.
+pub struct Actor {
+    pub name: String,
+    pub role: &'static str,
+    /// URL of their picture
+    pub thumb: Option<String>,
+}
+
+impl Actor {
+    /// The uploading channel (with its avatar, when known) followed by the creators and artists
+    /// yt-dlp lists, each name once.
+    pub fn of(entry: &CatalogueEntry, avatar: Option<&str>) -> Vec<Actor> {
+        let json = &entry.json;
+        let mut actors = vec![Actor {
+            name: json.channel_name().to_string(),
+            role: "Uploader",
+            thumb: avatar.map(str::to_string),
+        }];
+
+        // `creator` and `artist` are the older, comma separated forms of the lists.
+        let names = |list: &[String], joined: &Option<String>| {
+            let joined = joined.iter().flat_map(|names| names.split(','));
+            list.iter()
+                .map(String::as_str)
+                .chain(joined)
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let credited = names(&json.creators, &json.creator)
+            .into_iter()
+            .map(|name| (name, "Creator"))
+            .chain(
+                names(&json.artists, &json.artist)
+                    .into_iter()
+                    .map(|name| (name, "Artist")),
+            );
+        for (name, role) in credited {
+            if !actors.iter().any(|actor| actor.name == name) {
+                actors.push(Actor {
+                    name,
+                    role,
+                    thumb: None,
+                });
+            }
+        }
+
+        actors
+    }
+}
+
+/// Kodi/Emby `episodedetails` NFO describing a single episode.
+pub struct EpisodeNfo<'a> {
+    pub entry: &'a CatalogueEntry,
+    pub title: &'a str,
+    pub season: usize,
+    pub episode: usize,
+    pub plot: Option<&'a str>,
+    pub actors: &'a [Actor],
+}
+
+impl EpisodeNfo<'_> {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+        out.push_str("<episodedetails>\n");
+        element(&mut out, "title", self.title);
+        original_title(&mut out, self.entry, self.title);
+        element(&mut out, "sorttitle", &sort_title(self.title));
+        element(&mut out, "season", &self.season.to_string());
+        element(&mut out, "episode", &self.episode.to_string());
+        let date = self.entry.date.format("%Y-%m-%d").to_string();
+        element(&mut out, "aired", &date);
+        element(&mut out, "premiered", &date);
+        if let Some(minutes) = runtime(self.entry) {
+            element(&mut out, "runtime", &minutes.to_string());
+        }
+        // The uploading channel, also in `--single-show` mode where the show is named otherwise.
+        element(&mut out, "studio", self.entry.json.channel_name());
+        language(&mut out, self.entry);
+        if let Some(plot) = self.plot {
+            element(&mut out, "plot", plot.trim());
+        }
+        actors(&mut out, self.actors);
+        let _ = writeln!(
+            out,
+            "  <uniqueid type=\"youtube\" default=\"true\">{}</uniqueid>",
+            escape(&self.entry.json.id)
+        );
+        out.push_str("</episodedetails>\n");
+
+        out
+    }
+}
+
+/// Kodi/Emby `musicvideo` NFO, the channel being the artist.
+pub struct MusicVideoNfo<'a> {
+    pub entry: &'a CatalogueEntry,
+    pub title: &'a str,
+    pub artist: &'a str,
+    pub plot: Option<&'a str>,
+    pub actors: &'a [Actor],
+}
+
+impl MusicVideoNfo<'_> {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+        out.push_str("<musicvideo>\n");
+        element(&mut out, "title", self.title);
+        original_title(&mut out, self.entry, self.title);
+        element(&mut out, "sorttitle", &sort_title(self.title));
+        element(&mut out, "artist", self.artist);
+        language(&mut out, self.entry);
+        element(&mut out, "year", &self.entry.date.format("%Y").to_string());
+        element(
+            &mut out,
+            "premiered",
+            &self.entry.date.format("%Y-%m-%d").to_string(),
+        );
+        if let Some(plot) = self.plot {
+            element(&mut out, "plot", plot.trim());
+        }
+        actors(&mut out, self.actors);
+        let _ = writeln!(
+            out,
+            "  <uniqueid type=\"youtube\" default=\"true\">{}</uniqueid>",
+            escape(&self.entry.json.id)
+        );
+        out.push_str("</musicvideo>\n");
+
+        out
+    }
+}
+
+/// Length in whole minutes as Kodi and Emby expect it, at least one for anything shorter.
+fn runtime(entry: &CatalogueEntry) -> Option<u64> {
+    let seconds = entry.json.duration.filter(|d| *d > 0.0)?;
+    Some(((seconds / 60.0).round() as u64).max(1))
+}
+
+/// The title as uploaded, when title rules or stripping changed it.
+fn original_title(out: &mut String, entry: &CatalogueEntry, title: &str) {
+    let original = entry.get_title();
+    if original != title {
+        element(out, "originaltitle", original);
+    }
+}
+
+/// The spoken language, as yt-dlp reports it.
+fn language(out: &mut String, entry: &CatalogueEntry) {
+    if let Some(language) = entry.json.language.as_deref().filter(|l| !l.is_empty()) {
+        element(out, "language", language);
+    }
+}
+
+fn actors(out: &mut String, actors: &[Actor]) {
+    for actor in actors {
+        out.push_str("  <actor>\n");
+        let _ = writeln!(out, "    <name>{}</name>", escape(&actor.name));
+        let _ = writeln!(out, "    <role>{}</role>", actor.role);
+        if let Some(thumb) = &actor.thumb {
+            let _ = writeln!(out, "    <thumb>{}</thumb>", escape(thumb));
+        }
+        out.push_str("  </actor>\n");
+    }
+}
+
+fn element(out: &mut String, name: &str, value: &str) {
+    let _ = writeln!(out, "  <{name}>{}</{name}>", escape(value));
+}
+
+pub fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than whitespace aren't allowed in XML 1.0.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+
+    out
+}
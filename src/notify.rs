@@ -0,0 +1,92 @@
+use serde_json::json;
+
+use crate::{config::Notification, summary::Report};
+
+const DISCORD_LIMIT: usize = 2000;
+const TELEGRAM_LIMIT: usize = 4096;
+
+/// POSTs the run report as JSON.
+pub fn webhook(url: &str, report: &Report) -> anyhow::Result<()> {
+    ureq::post(url)
+        .header("Content-Type", "application/json")
+        .send(serde_json::to_string(report)?)?;
+
+    Ok(())
+}
+
+/// Sends the report through a configured backend. Chat backends only get a message when there
+/// are new episodes or errors.
+pub fn send(notification: &Notification, report: &Report) -> anyhow::Result<()> {
+    let (url, body) = match (notification, message(report)) {
+        (Notification::Webhook { url }, _) => return webhook(url, report),
+        (_, None) => return Ok(()),
+        (Notification::Discord { webhook_url }, Some(text)) => (
+            webhook_url.clone(),
+            json!({ "content": truncate(&text, DISCORD_LIMIT) }),
+        ),
+        (Notification::Telegram { bot_token, chat_id }, Some(text)) => (
+            format!("https://api.telegram.org/bot{bot_token}/sendMessage"),
+            json!({ "chat_id": chat_id, "text": truncate(&text, TELEGRAM_LIMIT) }),
+        ),
+    };
+
+    ureq::post(&url)
+        .header("Content-Type", "application/json")
+        .send(body.to_string())?;
+
+    Ok(())
+}
+
+fn message(report: &Report) -> Option<String> {
+    let mut lines = Vec::new();
+
+    for channel in &report.channels {
+        match channel.summary.new_episodes.len() {
+            0 => {}
+            1 => lines.push(format!("1 new episode organized for {}", channel.channel)),
+            n => lines.push(format!(
+                "{n} new episodes organized for {}",
+                channel.channel
+            )),
+        }
+    }
+
+    for episode in &report.pruned {
+        lines.push(format!("Pruned {} / {}", episode.channel, episode.title));
+    }
+
+    for video in &report.broken_media {
+        lines.push(format!(
+            "Broken download {} / {}",
+            video.channel, video.title
+        ));
+    }
+
+    for unreadable in &report.inaccessible {
+        lines.push(format!("Can't read {}", unreadable.path.display()));
+    }
+
+    for err in &report.errors {
+        lines.push(format!("Error: {err}"));
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    if report.dry_run {
+        lines.insert(0, "Dry run:".to_string());
+    }
+
+    Some(lines.join("\n"))
+}
+
+fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+
+    let mut truncated = text.chars().take(limit - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,357 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    LinkMode,
+    artwork::Thumbnail,
+    classify::ContentKind,
+    copy_file,
+    error::OrganizerError,
+    manifest::Manifest,
+    reflink,
+    state::State,
+    summary::Summary,
+    term::{self, Color},
+    trash,
+};
+
+/// A single change to the filesystem, in the order a run would make it.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    CreateDir {
+        path: PathBuf,
+    },
+    /// Places a source file of a video
+    Link {
+        #[serde(with = "crate::os_path")]
+        source: PathBuf,
+        target: PathBuf,
+        mode: LinkMode,
+        /// What a symlink holds when it differs from `source`, with `--link-prefix-map`
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::os_path::option"
+        )]
+        points_to: Option<PathBuf>,
+        id: String,
+        kind: ContentKind,
+    },
+    /// Hard links a byte-identical file placed earlier, with `--dedupe link`
+    HardLink {
+        existing: PathBuf,
+        target: PathBuf,
+        id: String,
+        kind: ContentKind,
+    },
+    /// Also of source files, with `--prune-sources`
+    Remove {
+        #[serde(with = "crate::os_path")]
+        path: PathBuf,
+    },
+    /// Generated files: NFOs, collections and markers
+    Write {
+        path: PathBuf,
+        content: String,
+    },
+    /// Channel fanart fetched from a thumbnail URL
+    Download {
+        id: String,
+        thumbnail: Thumbnail,
+        target: PathBuf,
+    },
+}
+
+/// What a run would do to one target root.
+#[derive(Serialize, Deserialize)]
+pub struct LibraryPlan {
+    pub target: PathBuf,
+    pub operations: Vec<Operation>,
+    /// The manifest once the operations are done, files missing after applying are dropped
+    pub manifest: Manifest,
+    /// Numbers to keep, with `--stable-numbering`
+    pub state: Option<State>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Plan {
+    pub libraries: Vec<LibraryPlan>,
+}
+
+/// Collects the operations of a dry run into a plan, a default (disabled) recorder drops them.
+#[derive(Clone, Default)]
+pub struct Recorder(Option<Rc<RefCell<Plan>>>);
+
+impl Recorder {
+    pub fn enabled() -> Self {
+        Self(Some(Rc::default()))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Starts the operations of another target root.
+    pub fn start(&self, target: &Path) {
+        if let Some(plan) = &self.0 {
+            plan.borrow_mut().libraries.push(LibraryPlan {
+                target: target.to_path_buf(),
+                operations: Vec::new(),
+                manifest: Manifest::default(),
+                state: None,
+            });
+        }
+    }
+
+    pub fn record(&self, operation: impl FnOnce() -> Operation) {
+        if let Some(plan) = &self.0
+            && let Some(library) = plan.borrow_mut().libraries.last_mut()
+        {
+            library.operations.push(operation());
+        }
+    }
+
+    /// Stores the manifest and state the current target root ends up with.
+    pub fn finish(&self, manifest: Manifest, state: Option<State>) {
+        if let Some(plan) = &self.0
+            && let Some(library) = plan.borrow_mut().libraries.last_mut()
+        {
+            library.manifest = manifest;
+            library.state = state;
+        }
+    }
+
+    /// Calls `f` with what was recorded for the current target root.
+    pub fn current<R>(&self, f: impl FnOnce(&LibraryPlan) -> R) -> Option<R> {
+        let plan = self.0.as_ref()?.borrow();
+        plan.libraries.last().map(f)
+    }
+
+    /// Writes the plan, returns the number of operations in it.
+    pub fn save(&self, path: &Path) -> anyhow::Result<usize> {
+        let Some(plan) = &self.0 else {
+            return Ok(0);
+        };
+        let plan = plan.borrow();
+        crate::write_json(path, &*plan)?;
+
+        Ok(plan.libraries.iter().map(|l| l.operations.len()).sum())
+    }
+
+    /// Hands out the plan recorded so far and starts over.
+    pub fn take(&self) -> Plan {
+        self.0
+            .as_ref()
+            .map(|plan| std::mem::take(&mut *plan.borrow_mut()))
+            .unwrap_or_default()
+    }
+}
+
+/// Executes a plan file written by `plan`.
+pub fn apply(path: &Path, dry_run: bool, force: bool, verbose: bool) -> anyhow::Result<Summary> {
+    let file = File::open(path).map_err(OrganizerError::io(path))?;
+    let plan: Plan = serde_json::from_reader(BufReader::new(file))
+        .map_err(|err| OrganizerError::json(path, err))?;
+
+    plan.apply(dry_run, force, verbose)
+}
+
+impl Plan {
+    /// Executes the operations, then saves the manifest and state of every target root. Unless
+    /// `force` is set, fails before changing anything when a target's filesystem has no room for
+    /// the files to copy.
+    pub fn apply(self, dry_run: bool, force: bool, verbose: bool) -> anyhow::Result<Summary> {
+        // Sources may have moved since planning, better to find out before changing anything.
+        let missing = self
+            .libraries
+            .iter()
+            .flat_map(|library| &library.operations)
+            .filter_map(|op| match op {
+                Operation::Link { source, .. } if source.symlink_metadata().is_err() => {
+                    Some(source)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = missing.first() {
+            bail!(
+                "{} source files of the plan are missing, e.g. {first:?}; plan again",
+                missing.len()
+            );
+        }
+
+        if !dry_run {
+            for library in &self.libraries {
+                let needed = library
+                    .operations
+                    .iter()
+                    .filter_map(|op| match op {
+                        Operation::Link {
+                            source,
+                            mode: LinkMode::Copy,
+                            ..
+                        } => source.metadata().ok(),
+                        _ => None,
+                    })
+                    .map(|meta| meta.len())
+                    .sum();
+                crate::space::check(&library.target, needed, force)?;
+
+                let removed = library
+                    .operations
+                    .iter()
+                    .filter(|op| matches!(op, Operation::Remove { .. }))
+                    .count();
+                if removed > 0
+                    && !crate::confirm::confirm(&format!(
+                        "The plan removes {removed} files from {:?}, go ahead?",
+                        library.target
+                    ))?
+                {
+                    bail!("Stopped, nothing was changed");
+                }
+            }
+        }
+
+        let mut summary = Summary::default();
+        for library in self.libraries {
+            let LibraryPlan {
+                target,
+                operations,
+                mut manifest,
+                state,
+            } = library;
+
+            for op in &operations {
+                if crate::signals::interrupted() {
+                    break;
+                }
+                execute(op, &target, &mut manifest, &mut summary, dry_run, verbose)
+                    .with_context(|| format!("Can't apply the plan for {target:?}"))?;
+            }
+
+            if dry_run {
+                continue;
+            }
+
+            manifest
+                .links
+                .retain(|link, _| target.join(link).symlink_metadata().is_ok());
+            manifest.save(&target)?;
+            if let Some(state) = &state {
+                state.save(&target)?;
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+fn execute(
+    op: &Operation,
+    root: &Path,
+    manifest: &mut Manifest,
+    summary: &mut Summary,
+    dry_run: bool,
+    verbose: bool,
+) -> anyhow::Result<()> {
+    let (action, path) = match op {
+        Operation::CreateDir { path } => ("Creating", path),
+        Operation::Link { target, .. } | Operation::HardLink { target, .. } => ("Placing", target),
+        Operation::Remove { path } => ("Removing", path),
+        Operation::Write { path, .. } => ("Writing", path),
+        Operation::Download { target, .. } => ("Downloading", target),
+    };
+    let color = match op {
+        Operation::Remove { .. } => Color::Yellow,
+        _ => Color::Green,
+    };
+    let done = match op {
+        Operation::CreateDir { path } => path.is_dir(),
+        Operation::Write { path, content } => {
+            std::fs::read_to_string(path).is_ok_and(|existing| existing == *content)
+        }
+        Operation::Link { target, .. } | Operation::HardLink { target, .. } => {
+            target.symlink_metadata().is_ok()
+        }
+        Operation::Remove { .. } | Operation::Download { .. } => false,
+    };
+    if done {
+        return Ok(());
+    }
+
+    if verbose {
+        term::say(color, action, path.display());
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    let result = match op {
+        Operation::CreateDir { path } => std::fs::create_dir_all(path),
+        Operation::Link {
+            source,
+            target,
+            mode,
+            points_to,
+            ..
+        } => match mode {
+            LinkMode::Symlink => {
+                std::os::unix::fs::symlink(points_to.as_ref().unwrap_or(source), target)
+            }
+            LinkMode::Reflink => match reflink::reflink(source, target) {
+                Err(err) if err.kind() != ErrorKind::AlreadyExists => copy_file(source, target),
+                result => result,
+            },
+            LinkMode::Copy => copy_file(source, target),
+        },
+        Operation::HardLink {
+            existing, target, ..
+        } => std::fs::hard_link(existing, target),
+        Operation::Remove { path } => match trash::remove(path, root) {
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            result => result,
+        },
+        Operation::Write { path, content } => {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(OrganizerError::io(dir))?;
+            }
+            std::fs::write(path, content)
+        }
+        Operation::Download {
+            id,
+            thumbnail,
+            target,
+        } => {
+            let cached = crate::artwork::fetch(id, thumbnail)?;
+            if let Ok(link) = target.strip_prefix(root) {
+                manifest.record(link.to_path_buf(), cached.clone(), id, None);
+            }
+            std::fs::copy(&cached, target).map(|_| ())
+        }
+    };
+
+    result.map_err(OrganizerError::io(path))?;
+
+    match op {
+        Operation::CreateDir { .. } => summary.directories += 1,
+        Operation::Link { .. } | Operation::HardLink { .. } => summary.links += 1,
+        Operation::Write { path, .. } if path.extension().is_some_and(|ext| ext == "nfo") => {
+            summary.nfo += 1
+        }
+        Operation::Download { .. } => summary.artwork += 1,
+        Operation::Remove { .. } | Operation::Write { .. } => {}
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+use crate::{
+    BuilderOptions, CatalogueEntry, SeasonedStructure,
+    collection::media_links,
+    events::Event,
+    manifest::Manifest,
+    plan::Operation,
+    term::{self, Color},
+};
+
+/// How many of the newest episodes across all channels `Latest 50.m3u` lists.
+const LATEST: usize = 50;
+
+/// The episodes of a channel in the order they're numbered, taken before the structure is
+/// consumed by the builder.
+pub struct ChannelPlaylist<'a> {
+    name: String,
+    seasons: Vec<(usize, Vec<&'a CatalogueEntry>)>,
+}
+
+impl<'a> ChannelPlaylist<'a> {
+    pub fn of(structure: &SeasonedStructure<'a>) -> Self {
+        Self {
+            name: structure.channel_name.clone(),
+            seasons: structure
+                .seasons
+                .iter()
+                .map(|season| {
+                    let videos = season.videos.iter().map(|ep| ep.entry).collect();
+                    (season.number, videos)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes `<Channel>.m3u` and a `Season NN.m3u` per season into the folder of every channel,
+/// plus `Latest 50.m3u` into the target, listing the media files the manifest places there.
+/// Entries are relative to the playlist, so the library can be mounted anywhere. Returns how many
+/// playlists were written or updated.
+pub fn write(
+    channels: &[ChannelPlaylist],
+    target: &Path,
+    manifest: &Manifest,
+    options: &BuilderOptions,
+) -> anyhow::Result<usize> {
+    let mut placed = Vec::new();
+    for channel in channels {
+        let episodes = channel
+            .seasons
+            .iter()
+            .map(|(number, videos)| {
+                let links = videos
+                    .iter()
+                    .flat_map(|entry| {
+                        media_links(entry, target, manifest)
+                            .into_iter()
+                            .map(move |link| (*entry, link))
+                    })
+                    .collect_vec();
+                (*number, links)
+            })
+            .filter(|(_, links)| !links.is_empty())
+            .collect_vec();
+        if let Some(dir) = channel_dir(episodes.iter().flat_map(|(_, links)| links), target) {
+            placed.push((channel, dir, episodes));
+        }
+    }
+
+    let mut playlists = BTreeMap::<PathBuf, String>::new();
+    let mut latest = Vec::new();
+    for (channel, dir, episodes) in &placed {
+        // Playlists of channels sharing a folder must not overwrite each other's.
+        let name = options
+            .naming
+            .normalization
+            .apply(&channel.name.replace('/', "_"))
+            .into_owned();
+        let shared = placed.iter().filter(|(_, other, _)| other == dir).count() > 1;
+        let prefix = if shared || dir == target {
+            format!("{name} - ")
+        } else {
+            String::new()
+        };
+
+        let all = episodes
+            .iter()
+            .flat_map(|(_, links)| links)
+            .cloned()
+            .collect_vec();
+        playlists.insert(dir.join(format!("{name}.m3u")), render(dir, &all));
+        for (number, links) in episodes {
+            let path = dir.join(format!("{prefix}Season {number:02}.m3u"));
+            playlists.insert(path, render(dir, links));
+        }
+
+        latest.extend(all);
+    }
+
+    latest.sort_by(|(a, a_link), (b, b_link)| {
+        b.date
+            .cmp(&a.date)
+            .then_with(|| options.sort.same_time(a, b))
+            .then_with(|| a_link.cmp(b_link))
+    });
+    latest.truncate(LATEST);
+    if !latest.is_empty() {
+        playlists.insert(
+            target.join(format!("Latest {LATEST}.m3u")),
+            render(target, &latest),
+        );
+    }
+
+    let mut written = 0;
+    for (path, content) in playlists {
+        if std::fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+            continue;
+        }
+
+        written += 1;
+        options.events.emit(Event::Playlist { path: &path });
+
+        if options.verbose {
+            term::say(Color::Green, "Writing", path.display());
+        }
+
+        if options.dry_run {
+            options.plan.record(|| Operation::Write { path, content });
+            continue;
+        }
+
+        std::fs::write(&path, content)?;
+    }
+
+    Ok(written)
+}
+
+/// The top folder all files of a channel share, the target itself when the layout spreads them
+/// over several. `None` for a channel without placed files.
+fn channel_dir<'a>(
+    mut links: impl Iterator<Item = &'a (&'a CatalogueEntry, PathBuf)>,
+    target: &Path,
+) -> Option<PathBuf> {
+    let first_component = |link: &Path| {
+        let mut components = link.strip_prefix(target).ok()?.components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), Some(_)) => Some(target.join(name)),
+            _ => None,
+        }
+    };
+
+    let (_, first) = links.next()?;
+    let dir = first_component(first);
+    if dir.is_some() && links.all(|(_, link)| first_component(link) == dir) {
+        dir
+    } else {
+        Some(target.to_path_buf())
+    }
+}
+
+fn render(dir: &Path, links: &[(&CatalogueEntry, PathBuf)]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for (entry, link) in links {
+        let duration = entry
+            .json
+            .duration
+            .map_or(-1, |seconds| seconds.round() as i64);
+        let _ = writeln!(
+            out,
+            "#EXTINF:{duration},{} - {}",
+            entry.json.channel_name(),
+            entry.json.title
+        );
+        let path = link.strip_prefix(dir).unwrap_or(link);
+        let _ = writeln!(out, "{}", path.to_string_lossy());
+    }
+
+    out
+}
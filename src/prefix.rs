@@ -0,0 +1,53 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Deserializer};
+
+/// Rewrites where symlinks point, written as `/mnt/downloads=/data/youtube`, for a media server
+/// (say in a container) that sees SOURCE under another path than the organizer.
+#[derive(Clone)]
+pub struct PrefixMap {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+impl FromStr for PrefixMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Prefix map {s:?} isn't in the form `FROM=TO`"))?;
+        if from.is_empty() || to.is_empty() {
+            return Err(anyhow!("Prefix map {s:?} needs both a FROM and a TO path"));
+        }
+
+        Ok(Self {
+            from: PathBuf::from(from),
+            to: PathBuf::from(to),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for PrefixMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// The path a symlink to `source` holds: rewritten by the first map whose FROM contains it,
+/// unchanged when none does.
+pub fn link_target<'a>(maps: &[PrefixMap], source: &'a Path) -> Cow<'a, Path> {
+    maps.iter()
+        .find_map(|map| {
+            let rest = source.strip_prefix(&map.from).ok()?;
+            Some(Cow::Owned(map.to.join(rest)))
+        })
+        .unwrap_or(Cow::Borrowed(source))
+}
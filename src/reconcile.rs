@@ -0,0 +1,83 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+use crate::manifest::Manifest;
+
+/// Index of files already present in a channel directory of the target, used to recognise
+/// episodes the user renamed by hand. Files are told apart by their extension, so the NFO
+/// generated from an info.json isn't taken for a link to it.
+#[derive(Default)]
+pub struct TargetIndex {
+    by_source: HashMap<(PathBuf, OsString), PathBuf>,
+    by_id: HashMap<(String, OsString), PathBuf>,
+}
+
+impl TargetIndex {
+    pub fn build(dir: &Path, root: &Path, manifest: &Manifest) -> Self {
+        let mut index = TargetIndex::default();
+
+        for e in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let path = e.path();
+            let ext = path.extension().unwrap_or_default().to_os_string();
+
+            if e.path_is_symlink() {
+                if let Ok(source) = std::fs::read_link(path) {
+                    index
+                        .by_source
+                        .insert((source, ext.clone()), path.to_path_buf());
+                }
+            } else if !e.file_type().is_file() {
+                continue;
+            }
+
+            if let Some(entry) = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(|link| manifest.links.get(link))
+            {
+                index
+                    .by_source
+                    .entry((entry.source.clone(), ext.clone()))
+                    .or_insert_with(|| path.to_path_buf());
+            }
+
+            if let Some(id) = embedded_id(path) {
+                index.by_id.insert((id, ext), path.to_path_buf());
+            }
+        }
+
+        index
+    }
+
+    /// Finds an existing file in the target holding `source`, either because it links to it or
+    /// because its name carries the video id in brackets.
+    pub fn find(&self, source: &Path, id: &str) -> Option<&PathBuf> {
+        let ext = source.extension().unwrap_or_default().to_os_string();
+        self.by_source
+            .get(&(source.to_path_buf(), ext.clone()))
+            .or_else(|| self.by_id.get(&(id.to_string(), ext)))
+    }
+
+    /// Finds the NFO generated for a video from its info.json.
+    pub fn find_nfo(&self, info_json: Option<&Path>, id: &str) -> Option<&PathBuf> {
+        let ext = OsString::from("nfo");
+        info_json
+            .and_then(|info_json| self.by_source.get(&(info_json.to_path_buf(), ext.clone())))
+            .or_else(|| self.by_id.get(&(id.to_string(), ext)))
+    }
+}
+
+/// Extracts a `[video id]` marker from the end of a file stem, as used by yt-dlp's default
+/// output template.
+fn embedded_id(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let stem = stem.strip_suffix(']')?;
+    let start = stem.rfind('[')?;
+
+    Some(stem[start + 1..].to_string())
+}
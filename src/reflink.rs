@@ -0,0 +1,40 @@
+use std::{io, path::Path};
+
+/// Creates `target` as a copy-on-write clone of `source`. Fails with `Unsupported` (or the
+/// filesystem's own error) when the filesystem can't share extents between the two paths.
+#[cfg(target_os = "linux")]
+pub fn reflink(source: &Path, target: &Path) -> io::Result<()> {
+    use std::{fs::File, os::fd::AsRawFd};
+
+    let src = File::open(source)?;
+    let dst = File::create_new(target)?;
+
+    let ret = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        drop(dst);
+        let _ = std::fs::remove_file(target);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn reflink(source: &Path, target: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let src = CString::new(source.as_os_str().as_bytes())?;
+    let dst = CString::new(target.as_os_str().as_bytes())?;
+
+    if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn reflink(_source: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
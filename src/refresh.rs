@@ -0,0 +1,151 @@
+use std::{fs::File, path::Path, process::Command};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    CatalogueEntry, VideoCatalogue,
+    filename::Confidence,
+    is_gzip, signals,
+    term::{self, Color},
+};
+
+/// Metadata an entry is expected to have, anything missing one of these is refreshed when no ids
+/// are given.
+fn incomplete(entry: &CatalogueEntry) -> bool {
+    let json = &entry.json;
+    entry.guessed.is_some()
+        || (json.channel.is_none() && json.uploader.is_none())
+        || json.duration.is_none()
+        || json.description.is_none()
+        || json.thumbnails.is_empty()
+}
+
+/// Downloads the info.json of the entries with the given ids (or of the incomplete ones) again
+/// with `yt-dlp --skip-download`, overwriting the old one in the source. Media organized from its
+/// file name gets an info.json next to it. Returns how many were refreshed.
+pub fn run(
+    catalogue: &VideoCatalogue,
+    ids: &[String],
+    yt_dlp: &Path,
+    dry_run: bool,
+    verbose: bool,
+) -> anyhow::Result<usize> {
+    let entries = if ids.is_empty() {
+        catalogue
+            .entries()
+            .filter(|entry| incomplete(entry))
+            .collect()
+    } else {
+        let mut entries = Vec::new();
+        for id in ids {
+            let Some(entry) = catalogue.entries().find(|entry| entry.json.id == *id) else {
+                bail!("No video with the id {id:?} in the source");
+            };
+            entries.push(entry);
+        }
+        entries
+    };
+
+    let mut refreshed = 0;
+    for entry in entries {
+        if signals::interrupted() {
+            bail!("Interrupted while refreshing metadata");
+        }
+        // A made up id would fetch some other video, or nothing.
+        if entry.guessed == Some(Confidence::Low) {
+            if verbose {
+                term::print(format_args!(
+                    "Not refreshing {:?}, its file name has no video id",
+                    entry.path[0]
+                ));
+            }
+            continue;
+        }
+
+        let (info_json, stem) = match entry.info_json() {
+            Some(info_json) => (info_json.clone(), info_json_stem(info_json)?),
+            None => {
+                let media = &entry.path[0];
+                let stem = media.file_stem().unwrap_or_default().to_string_lossy();
+                (
+                    media.with_file_name(format!("{stem}.info.json")),
+                    stem.into_owned(),
+                )
+            }
+        };
+        let url = entry
+            .json
+            .webpage_url
+            .clone()
+            .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", entry.json.id));
+
+        if verbose {
+            term::print(format_args!("Refreshing {info_json:?} from {url}"));
+        }
+        if dry_run {
+            refreshed += 1;
+            continue;
+        }
+
+        let dir = info_json.parent().unwrap_or(Path::new("."));
+        // `%(ext)s` of the info.json is `info.json`, keeping the stem the media was saved under.
+        let output = dir.join(format!("{}.%(ext)s", stem.replace('%', "%%")));
+        let mut command = Command::new(yt_dlp);
+        if !verbose {
+            command.arg("--quiet");
+        }
+        let status = command
+            .args([
+                "--skip-download",
+                "--write-info-json",
+                "--no-write-playlist-metafiles",
+            ])
+            .arg("--output")
+            .arg(&output)
+            .arg("--")
+            .arg(&url)
+            .status()
+            .with_context(|| format!("Can't run {yt_dlp:?}, is yt-dlp installed?"))?;
+        if !status.success() {
+            let message = format!(
+                "Refreshing {} failed ({status}), keeping the old metadata",
+                entry.json.id
+            );
+            term::error(Color::Red, message);
+            continue;
+        }
+
+        if is_gzip(&info_json) {
+            compress(&dir.join(format!("{stem}.info.json")), &info_json)?;
+        }
+        refreshed += 1;
+    }
+
+    Ok(refreshed)
+}
+
+/// `name` of `name.info.json` or `name.info.json.gz`.
+fn info_json_stem(info_json: &Path) -> anyhow::Result<String> {
+    let name = info_json.file_name().unwrap_or_default().to_string_lossy();
+    match name
+        .strip_suffix(".gz")
+        .unwrap_or(&name)
+        .strip_suffix(".info.json")
+    {
+        Some(stem) => Ok(stem.to_string()),
+        None => bail!("{info_json:?} isn't named like a yt-dlp info.json"),
+    }
+}
+
+/// Replaces the gzipped info.json with the freshly written plain one, so the source doesn't end
+/// up with both.
+fn compress(plain: &Path, gzipped: &Path) -> anyhow::Result<()> {
+    let mut input = File::open(plain)?;
+    let mut encoder =
+        flate2::write::GzEncoder::new(File::create(gzipped)?, flate2::Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(plain)?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    artwork,
+    plan::{LibraryPlan, Operation},
+    term, throttle,
+};
+
+const SCHEME: &str = "ssh://";
+
+/// A target on another machine, `ssh://[user@]host[:port]/path`, written to with the system's
+/// `sftp` client (so keys and `~/.ssh/config` apply). Experimental.
+///
+/// The structure is built as symlinks in a local staging directory, which also keeps the manifest
+/// and state; only the changes a run makes there are uploaded.
+pub struct Remote {
+    /// The target as given
+    pub url: PathBuf,
+    /// `[user@]host` as given to `sftp`
+    destination: String,
+    port: Option<u16>,
+    root: String,
+}
+
+impl Remote {
+    /// `None` for local targets.
+    pub fn parse(target: &Path) -> anyhow::Result<Option<Self>> {
+        let Some(rest) = target.to_str().and_then(|t| t.strip_prefix(SCHEME)) else {
+            return Ok(None);
+        };
+
+        let (authority, root) = rest.split_once('/').unwrap_or((rest, ""));
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((destination, port)) => (
+                destination,
+                Some(
+                    port.parse()
+                        .with_context(|| format!("Invalid port in {target:?}"))?,
+                ),
+            ),
+            None => (authority, None),
+        };
+        if destination.is_empty() || destination.ends_with('@') {
+            bail!("{target:?} has no host, expected ssh://[user@]host[:port]/path");
+        }
+
+        Ok(Some(Self {
+            url: target.to_path_buf(),
+            destination: destination.to_string(),
+            port,
+            root: format!("/{}", root.trim_end_matches('/')),
+        }))
+    }
+
+    pub fn staging_dir(&self) -> PathBuf {
+        let name = format!("{}{}", self.destination, self.root).replace('/', "_");
+        artwork::cache_dir("remote").join(name)
+    }
+
+    /// Path on the remote machine of a path in the staging directory, `None` for anything
+    /// outside of it (such as removed source files or a local collections folder).
+    fn remote_path(&self, staging: &Path, path: &Path) -> Option<String> {
+        let rest = path.strip_prefix(staging).ok()?;
+        let mut remote = self.root.trim_end_matches('/').to_string();
+        for component in rest.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            remote.push('/');
+            remote.push_str(&name.to_string_lossy());
+        }
+
+        Some(remote)
+    }
+
+    /// Makes the changes of `plan`, recorded against the staging directory, on the remote
+    /// machine. Files are always uploaded as copies.
+    pub fn upload(&self, plan: &LibraryPlan, verbose: bool) -> anyhow::Result<()> {
+        if plan.operations.is_empty() {
+            return Ok(());
+        }
+
+        let staging = &plan.target;
+        let upload_dir = staging.join(".upload");
+        std::fs::create_dir_all(&upload_dir)?;
+
+        let mut batch = String::new();
+        let mut created = HashSet::new();
+        let mut mkdir = |batch: &mut String, dir: &str| {
+            // `sftp` creates one level at a time, errors for existing directories are ignored.
+            let mut path = String::new();
+            for part in dir.split('/').filter(|part| !part.is_empty()) {
+                path.push('/');
+                path.push_str(part);
+                if created.insert(path.clone()) {
+                    let _ = writeln!(batch, "-mkdir {}", quote(&path));
+                }
+            }
+        };
+        mkdir(&mut batch, &self.root);
+
+        let mut files = 0;
+        for (index, op) in plan.operations.iter().enumerate() {
+            match op {
+                Operation::CreateDir { path } => {
+                    if let Some(remote) = self.remote_path(staging, path) {
+                        mkdir(&mut batch, &remote);
+                    }
+                }
+                Operation::Link { source, target, .. } => {
+                    if let Some(remote) = self.remote_path(staging, target) {
+                        put(&mut batch, source, &remote);
+                        files += 1;
+                    }
+                }
+                Operation::HardLink {
+                    existing, target, ..
+                } => {
+                    if let (Some(existing), Some(remote)) = (
+                        self.remote_path(staging, existing),
+                        self.remote_path(staging, target),
+                    ) {
+                        let _ = writeln!(batch, "ln {} {}", quote(&existing), quote(&remote));
+                    }
+                }
+                Operation::Remove { path } => {
+                    if let Some(remote) = self.remote_path(staging, path) {
+                        let _ = writeln!(batch, "-rm {}", quote(&remote));
+                    }
+                }
+                Operation::Write { path, content } => {
+                    if let Some(remote) = self.remote_path(staging, path) {
+                        let local = upload_dir.join(index.to_string());
+                        std::fs::write(&local, content)?;
+                        put(&mut batch, &local, &remote);
+                        files += 1;
+                    }
+                }
+                Operation::Download {
+                    id,
+                    thumbnail,
+                    target,
+                } => {
+                    if let Some(remote) = self.remote_path(staging, target) {
+                        put(&mut batch, &artwork::fetch(id, thumbnail)?, &remote);
+                        files += 1;
+                    }
+                }
+            }
+        }
+
+        if verbose {
+            term::print(format_args!(
+                "Uploading {files} files to {}:{}",
+                self.destination, self.root
+            ));
+        }
+
+        let batch_file = upload_dir.join("batch");
+        std::fs::write(&batch_file, batch)?;
+
+        let mut sftp = Command::new("sftp");
+        sftp.arg("-q").arg("-b").arg(&batch_file);
+        if let Some(port) = self.port {
+            sftp.arg("-P").arg(port.to_string());
+        }
+        if let Some(limit) = throttle::limit() {
+            // In Kbit/s.
+            sftp.arg("-l").arg((limit * 8 / 1000).max(1).to_string());
+        }
+        let status = sftp
+            .arg(&self.destination)
+            .status()
+            .context("Can't run sftp, is OpenSSH installed?")?;
+
+        std::fs::remove_dir_all(&upload_dir)?;
+        if !status.success() {
+            bail!(
+                "Uploading to {}:{} failed ({status})",
+                self.destination,
+                self.root
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn put(batch: &mut String, local: &Path, remote: &str) {
+    let _ = writeln!(
+        batch,
+        "put {} {}",
+        quote(&local.to_string_lossy()),
+        quote(remote)
+    );
+}
+
+/// Quotes an argument of an `sftp` batch command.
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
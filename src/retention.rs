@@ -0,0 +1,233 @@
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use crate::{
+    CatalogueEntry, SeasonedStructure, confirm,
+    manifest::Manifest,
+    plan::{Operation, Recorder},
+    stats::human_size,
+    term::{self, Color},
+    trash,
+};
+
+/// A size given on the command line: plain bytes or a number with a decimal (`KB`, `MB`, `GB`,
+/// `TB`) or binary (`KiB`, `MiB`, `GiB`, `TiB`) unit.
+#[derive(Clone, Copy)]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+
+        let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" => 1_000,
+            "m" | "mb" => 1_000_000,
+            "g" | "gb" => 1_000_000_000,
+            "t" | "tb" => 1_000_000_000_000,
+            "kib" => 1 << 10,
+            "mib" => 1 << 20,
+            "gib" => 1 << 30,
+            "tib" => 1 << 40,
+            other => return Err(anyhow!("Unknown size unit {other:?}")),
+        };
+        let number: f64 = number.parse().map_err(|_| anyhow!("Invalid size {s:?}"))?;
+
+        Ok(ByteSize((number * multiplier as f64) as u64))
+    }
+}
+
+#[derive(Serialize)]
+pub struct PrunedEpisode {
+    pub channel: String,
+    pub title: String,
+    pub id: String,
+    /// Space freed by removing it
+    pub size: u64,
+}
+
+/// Size of all files of an episode.
+fn episode_size(entry: &CatalogueEntry) -> u64 {
+    entry
+        .path
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Space removing `paths` frees, as they are: symlinks count as the link, files hard linked
+/// between the paths once.
+fn freed_size<'a>(paths: impl Iterator<Item = &'a Path>) -> u64 {
+    let mut inodes = HashSet::new();
+    paths
+        .filter_map(|path| path.symlink_metadata().ok())
+        .filter(|meta| inodes.insert((meta.dev(), meta.ino())))
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Picks the oldest episodes, across all channels, that don't fit in `max` bytes once all newer
+/// episodes are in the library, and removes them from the structure.
+pub fn select<'a>(
+    structure: &mut Vec<SeasonedStructure<'a>>,
+    max: u64,
+) -> Vec<(String, &'a CatalogueEntry)> {
+    let mut episodes = structure
+        .iter()
+        .flat_map(|channel| {
+            channel
+                .seasons
+                .iter()
+                .flat_map(|season| season.videos.iter())
+                .map(|ep| (channel.channel_name.clone(), ep.entry))
+        })
+        .collect::<Vec<_>>();
+    episodes.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.date));
+
+    let mut total = 0;
+    let first_pruned = episodes.iter().position(|(_, entry)| {
+        total += episode_size(entry);
+        total > max
+    });
+    let Some(first_pruned) = first_pruned else {
+        return Vec::new();
+    };
+    let pruned = episodes.split_off(first_pruned);
+
+    let ids = pruned
+        .iter()
+        .map(|(_, entry)| entry.json.id.as_str())
+        .collect::<HashSet<_>>();
+    structure.retain_mut(|channel| channel.retain_videos(|e| !ids.contains(e.json.id.as_str())));
+
+    pruned
+}
+
+/// Removes everything the manifest places in `target` for the pruned episodes and, given the
+/// source directory, the downloaded files themselves.
+pub fn prune(
+    pruned: &[(String, &CatalogueEntry)],
+    target: &Path,
+    manifest: &mut Manifest,
+    source: Option<&Path>,
+    dry_run: bool,
+    verbose: bool,
+    plan: &Recorder,
+) -> anyhow::Result<Vec<PrunedEpisode>> {
+    let mut report = Vec::new();
+
+    let placed = |entry: &CatalogueEntry| {
+        manifest
+            .links
+            .values()
+            .any(|known| known.id == entry.json.id)
+    };
+    let episodes = pruned
+        .iter()
+        .filter(|(_, entry)| source.is_some() || placed(entry))
+        .count();
+    let downloads = match source {
+        Some(_) => " and their downloads",
+        None => "",
+    };
+    if !dry_run
+        && episodes > 0
+        && !confirm::confirm(&format!(
+            "Remove {episodes} episodes from {target:?}{downloads} to stay under \
+             --max-library-size?"
+        ))?
+    {
+        let message = format!("Nothing pruned from {target:?}");
+        term::error(Color::Yellow, message);
+        return Ok(report);
+    }
+
+    for (channel, entry) in pruned {
+        let links = manifest
+            .links
+            .iter()
+            .filter(|(_, known)| known.id == entry.json.id)
+            .map(|(link, _)| link.clone())
+            .collect::<Vec<_>>();
+
+        if links.is_empty() && source.is_none() {
+            continue;
+        }
+
+        let in_target = links
+            .iter()
+            .map(|link| target.join(link))
+            .collect::<Vec<_>>();
+        let downloads = source.map(|_| entry.path.as_slice()).unwrap_or_default();
+        let size = freed_size(in_target.iter().chain(downloads).map(PathBuf::as_path));
+
+        if verbose {
+            term::print(format_args!(
+                "Pruning: {channel} / {} ({})",
+                entry.get_title(),
+                human_size(size)
+            ));
+        }
+
+        for link in links {
+            remove(&target.join(&link), target, dry_run, verbose, plan)?;
+            if !dry_run {
+                manifest.remove(&link);
+            }
+        }
+
+        if let Some(source) = source {
+            for path in &entry.path {
+                remove(path, source, dry_run, verbose, plan)?;
+            }
+        }
+
+        report.push(PrunedEpisode {
+            channel: channel.clone(),
+            title: entry.get_title().to_string(),
+            id: entry.json.id.clone(),
+            size,
+        });
+    }
+
+    Ok(report)
+}
+
+fn remove(
+    path: &Path,
+    root: &Path,
+    dry_run: bool,
+    verbose: bool,
+    plan: &Recorder,
+) -> anyhow::Result<()> {
+    if verbose {
+        term::say(Color::Yellow, "Removing", path.display());
+    }
+
+    if dry_run {
+        plan.record(|| Operation::Remove {
+            path: path.to_path_buf(),
+        });
+        return Ok(());
+    }
+
+    match trash::remove(path, root) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(err)?,
+        _ => Ok(()),
+    }
+}
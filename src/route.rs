@@ -0,0 +1,117 @@
+use std::{path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+
+use crate::{CatalogueEntry, classify::ContentKind};
+
+#[derive(Clone)]
+enum Condition {
+    /// One of the YouTube categories of the video, case insensitive
+    Category(String),
+    Channel(String),
+    Kind(ContentKind),
+    /// Longer than this many seconds
+    LongerThan(f64),
+    /// Shorter than this many seconds
+    ShorterThan(f64),
+}
+
+/// Sends the videos matching a condition to another target root, written as
+/// `category=Music => /media/MusicVideos`, `channel=Some Podcast => /media/Podcasts` or
+/// `duration>1h => /media/Longform`.
+#[derive(Clone)]
+pub struct Route {
+    condition: Condition,
+    pub target: PathBuf,
+}
+
+impl FromStr for Route {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, target) = s
+            .split_once("=>")
+            .ok_or_else(|| anyhow!("Route {s:?} isn't in the form `CONDITION => TARGET`"))?;
+        let (condition, target) = (condition.trim(), target.trim());
+        if target.is_empty() {
+            bail!("Route {s:?} has no target");
+        }
+
+        let condition = if let Some(category) = condition.strip_prefix("category=") {
+            Condition::Category(category.trim().to_lowercase())
+        } else if let Some(channel) = condition.strip_prefix("channel=") {
+            Condition::Channel(channel.trim().to_string())
+        } else if let Some(kind) = condition.strip_prefix("kind=") {
+            Condition::Kind(
+                ContentKind::from_str(kind.trim(), true)
+                    .map_err(|_| anyhow!("Unknown content kind {kind:?} in route {s:?}"))?,
+            )
+        } else if let Some(duration) = condition.strip_prefix("duration>") {
+            Condition::LongerThan(parse_duration(duration)?)
+        } else if let Some(duration) = condition.strip_prefix("duration<") {
+            Condition::ShorterThan(parse_duration(duration)?)
+        } else {
+            bail!(
+                "Unknown route condition {condition:?}, expected category=NAME, channel=NAME, \
+                 kind=KIND, duration>LENGTH or duration<LENGTH"
+            );
+        };
+
+        Ok(Self {
+            condition,
+            target: PathBuf::from(target),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Route {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Seconds, optionally with an `s`, `m` or `h` unit.
+fn parse_duration(s: &str) -> anyhow::Result<f64> {
+    let s = s.trim();
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1.0),
+        Some((i, 'm')) => (&s[..i], 60.0),
+        Some((i, 'h')) => (&s[..i], 3600.0),
+        _ => (s, 1.0),
+    };
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration {s:?}"))?;
+
+    Ok(number * multiplier)
+}
+
+impl Route {
+    /// Sends every video of a channel to `target`, for the `"target"` of a channel in the config.
+    pub fn channel(name: &str, target: PathBuf) -> Self {
+        Self {
+            condition: Condition::Channel(name.to_string()),
+            target,
+        }
+    }
+
+    pub fn matches(&self, entry: &CatalogueEntry) -> bool {
+        let json = &entry.json;
+
+        match &self.condition {
+            Condition::Category(category) => json
+                .categories
+                .iter()
+                .any(|c| c.to_lowercase() == *category),
+            Condition::Channel(channel) => json.channel_name() == channel,
+            Condition::Kind(kind) => entry.kind == *kind,
+            Condition::LongerThan(seconds) => json.duration.is_some_and(|d| d > *seconds),
+            Condition::ShorterThan(seconds) => json.duration.is_some_and(|d| d < *seconds),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Timelike};
+
+/// Allowed values of one cron field.
+#[derive(Clone)]
+struct Field {
+    values: Vec<bool>,
+    /// `*` matches anything, this matters for the day of month / day of week rule
+    any: bool,
+}
+
+impl Field {
+    fn parse(s: &str, min: u32, max: u32, names: &[&str]) -> anyhow::Result<Self> {
+        // Sunday may also be written as 7, it's folded into 0 once the ranges are built.
+        let top = if max == 6 { 7 } else { max };
+        let mut values = vec![false; top as usize + 1];
+        let value = |v: &str| -> anyhow::Result<u32> {
+            if let Some(index) = names.iter().position(|name| name.eq_ignore_ascii_case(v)) {
+                return Ok(min + index as u32);
+            }
+            let n: u32 = v
+                .parse()
+                .map_err(|_| anyhow!("Invalid value {v:?} in cron field {s:?}"))?;
+            if n < min || n > top {
+                bail!("{n} is out of range {min}-{top} in cron field {s:?}");
+            }
+            Ok(n)
+        };
+
+        for part in s.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (
+                    range,
+                    step.parse::<u32>()
+                        .ok()
+                        .filter(|step| *step > 0)
+                        .ok_or_else(|| anyhow!("Invalid step in cron field {s:?}"))?,
+                ),
+                None => (part, 1),
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (value(start)?, value(end)?),
+                    None if step > 1 => (value(range)?, max),
+                    None => {
+                        let v = value(range)?;
+                        (v, v)
+                    }
+                },
+            };
+            if start > end {
+                bail!("Range {range:?} runs backwards in cron field {s:?}");
+            }
+
+            for v in (start..=end).step_by(step as usize) {
+                values[v as usize] = true;
+            }
+        }
+        if top > max {
+            values[0] |= values[7];
+            values.truncate(max as usize + 1);
+        }
+
+        Ok(Self {
+            values,
+            any: s == "*",
+        })
+    }
+
+    fn matches(&self, v: u32) -> bool {
+        self.values.get(v as usize).copied().unwrap_or(false)
+    }
+}
+
+/// A standard five field cron expression (`minute hour day-of-month month day-of-week`), e.g.
+/// `0 */6 * * *`, evaluated in the local time zone.
+#[derive(Clone)]
+pub struct Schedule {
+    minute: Field,
+    hour: Field,
+    day: Field,
+    month: Field,
+    weekday: Field,
+}
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!("Cron expression {s:?} must have 5 fields: minute hour day month weekday");
+        };
+
+        Ok(Self {
+            minute: Field::parse(minute, 0, 59, &[])?,
+            hour: Field::parse(hour, 0, 23, &[])?,
+            day: Field::parse(day, 1, 31, &[])?,
+            month: Field::parse(month, 1, 12, MONTHS)?,
+            weekday: Field::parse(weekday, 0, 6, WEEKDAYS)?,
+        })
+    }
+}
+
+impl Schedule {
+    fn matches_day(&self, time: &DateTime<Local>) -> bool {
+        let day = self.day.matches(time.day());
+        let weekday = self.weekday.matches(time.weekday().num_days_from_sunday());
+
+        // Like cron, a restricted day of month and day of week match when either does.
+        let day = match (self.day.any, self.weekday.any) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+
+        day && self.month.matches(time.month())
+    }
+
+    /// The first matching minute after `after`, `None` if there is none in the next five years
+    /// (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut time = start.naive_local();
+        let limit = time + Duration::days(5 * 366);
+
+        while time < limit {
+            let local = Local.from_local_datetime(&time).earliest();
+            let Some(local) = local else {
+                // Skipped by a daylight saving change.
+                time += Duration::minutes(1);
+                continue;
+            };
+
+            if !self.matches_day(&local) {
+                time = (time.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if !self.hour.matches(time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !self.minute.matches(time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(local);
+            }
+        }
+
+        None
+    }
+}
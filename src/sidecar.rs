@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{MEDIA_EXTENSIONS, THUMBNAIL_EXTENSIONS, error::OrganizerError};
+
+const SUBTITLE_EXTENSIONS: &[&str] = &["vtt", "srt", "ass", "ssa", "ttml", "srv3", "lrc"];
+const METADATA_EXTENSIONS: &[&str] = &["description", "nfo"];
+
+/// Leftovers of interrupted downloads and yt-dlp extras no media server understands.
+const DEFAULT_DENY: &[&str] = &["part", "ytdl", "temp", "tmp", "live_chat.json"];
+
+/// Decides which files sharing the stem of an info.json are placed next to the episode.
+/// Entries match the whole suffix after the stem (`en.vtt`) or its tail (`vtt`).
+#[derive(Clone)]
+pub struct SidecarFilter {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl Default for SidecarFilter {
+    fn default() -> Self {
+        SidecarFilter::new(Vec::new(), Vec::new())
+    }
+}
+
+impl SidecarFilter {
+    /// Empty lists fall back to the defaults: media, thumbnails, subtitles and descriptions are
+    /// allowed, partial downloads and live chat dumps denied.
+    pub fn new(allow: Vec<String>, deny: Vec<String>) -> Self {
+        let or_default = |list: Vec<String>, default: &[&[&str]]| {
+            if list.is_empty() {
+                default.concat().iter().map(|ext| ext.to_string()).collect()
+            } else {
+                list.into_iter()
+                    .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                    .collect()
+            }
+        };
+
+        Self {
+            allow: or_default(
+                allow,
+                &[
+                    MEDIA_EXTENSIONS,
+                    THUMBNAIL_EXTENSIONS,
+                    SUBTITLE_EXTENSIONS,
+                    METADATA_EXTENSIONS,
+                ],
+            ),
+            deny: or_default(deny, &[DEFAULT_DENY]),
+        }
+    }
+
+    /// Whether a file named `<stem>.<suffix>` belongs to the episode.
+    pub fn accepts(&self, suffix: &str) -> bool {
+        let suffix = suffix.to_lowercase();
+        let matches = |ext: &String| {
+            suffix == *ext
+                || suffix
+                    .strip_suffix(ext.as_str())
+                    .is_some_and(|rest| rest.ends_with('.'))
+        };
+
+        self.allow.iter().any(matches) && !self.deny.iter().any(matches)
+    }
+}
+
+/// Sorted names of the regular files of every directory sidecars were looked up in, so each
+/// directory is listed once per scan instead of once per info.json in it.
+#[derive(Default)]
+pub struct SiblingIndex {
+    dirs: HashMap<PathBuf, Vec<OsString>>,
+}
+
+impl SiblingIndex {
+    /// Files of `dir` whose name starts with `prefix`, in name order.
+    pub fn starting_with(
+        &mut self,
+        dir: &Path,
+        prefix: &OsStr,
+    ) -> anyhow::Result<impl Iterator<Item = &OsStr>> {
+        if !self.dirs.contains_key(dir) {
+            let names = Self::list(dir)?;
+            self.dirs.insert(dir.to_path_buf(), names);
+        }
+        let names = &self.dirs[dir];
+
+        let start = names.partition_point(|name| name.as_os_str() < prefix);
+        Ok(names[start..]
+            .iter()
+            .map(OsString::as_os_str)
+            .take_while(move |name| name.as_bytes().starts_with(prefix.as_bytes())))
+    }
+
+    fn list(dir: &Path) -> anyhow::Result<Vec<OsString>> {
+        let mut names = Vec::new();
+        for e in std::fs::read_dir(dir)
+            .map_err(OrganizerError::io(dir))?
+            .flatten()
+        {
+            if !e
+                .file_type()
+                .map_err(OrganizerError::io(&e.path()))?
+                .is_file()
+            {
+                continue;
+            }
+            names.push(e.file_name());
+        }
+        names.sort_unstable();
+
+        Ok(names)
+    }
+}
@@ -0,0 +1,52 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Exit code of a run stopped by SIGINT or SIGTERM, as shells report it for SIGINT.
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Makes SIGINT and SIGTERM only raise a flag checked between file operations, so a stopped run
+/// still saves the manifest and state. A second signal stops the process right away.
+#[cfg(unix)]
+pub fn install() {
+    extern "C" fn handle(signal: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+        }
+    }
+
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Sleeps for `duration` unless interrupted first, returns whether the whole time passed.
+pub fn sleep(duration: Duration) -> bool {
+    // Too far off to be represented, that's forever.
+    let until = Instant::now().checked_add(duration);
+
+    while !interrupted() {
+        let left = until.map_or(Duration::MAX, |until| {
+            until.saturating_duration_since(Instant::now())
+        });
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(200)));
+    }
+
+    false
+}
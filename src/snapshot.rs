@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Library, VideoCatalogue, classify::ContentKind};
+
+/// What the archive looked like at one point in time: every video of the source and where it
+/// would be organized, independent of what the target holds.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub taken: String,
+    #[serde(with = "crate::os_path")]
+    pub source: PathBuf,
+    /// SHA-256 of `videos`, equal for snapshots of an unchanged archive
+    pub hash: String,
+    pub videos: BTreeMap<String, VideoSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct VideoSnapshot {
+    pub channel: String,
+    pub title: String,
+    pub date: String,
+    pub kind: ContentKind,
+    /// The show, season and episode it's organized as, `None` when it's filtered out
+    pub placement: Option<Placement>,
+    /// Its files, relative to the source
+    #[serde(with = "crate::os_path::vec")]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct Placement {
+    pub show: String,
+    pub season: usize,
+    pub episode: usize,
+}
+
+impl std::fmt::Display for Placement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} S{:02}E{:02}", self.show, self.season, self.episode)
+    }
+}
+
+impl Snapshot {
+    pub fn take(source: &Path, catalogue: &VideoCatalogue, libraries: &[Library]) -> Self {
+        let mut placements = BTreeMap::new();
+        for channel in libraries.iter().flat_map(|library| &library.structure) {
+            for season in &channel.seasons {
+                for ep in &season.videos {
+                    placements
+                        .entry(ep.entry.json.id.as_str())
+                        .or_insert_with(|| Placement {
+                            show: channel.channel_name.clone(),
+                            season: season.number,
+                            episode: ep.number,
+                        });
+                }
+            }
+        }
+
+        let videos = catalogue
+            .entries()
+            .map(|entry| {
+                let video = VideoSnapshot {
+                    channel: entry.json.channel_name().to_string(),
+                    title: entry.get_title().to_string(),
+                    date: entry.date.to_string(),
+                    kind: entry.kind,
+                    placement: placements.get(entry.json.id.as_str()).cloned(),
+                    files: entry
+                        .path
+                        .iter()
+                        .map(|file| file.strip_prefix(source).unwrap_or(file).to_path_buf())
+                        .collect(),
+                };
+                (entry.json.id.clone(), video)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut hasher = Sha256::new();
+        // Serializing plain strings and numbers can't fail.
+        serde_json::to_writer(&mut hasher, &videos).expect("snapshot serializes");
+
+        Snapshot {
+            taken: Local::now().to_rfc3339(),
+            source: source.to_path_buf(),
+            hash: format!("{:x}", hasher.finalize()),
+            videos,
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("Can't open snapshot {path:?}"))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{path:?} isn't a snapshot"))
+    }
+
+    /// Prints the videos added, removed and changed since `old`.
+    pub fn diff(&self, old: &Snapshot) {
+        if self.hash == old.hash {
+            println!("Nothing changed since {}", old.taken);
+            return;
+        }
+
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        for (id, video) in &self.videos {
+            match old.videos.get(id) {
+                None => {
+                    added += 1;
+                    println!("+ {id} {}: {}{}", video.channel, video.title, at(video));
+                }
+                Some(before) if before != video => {
+                    changed += 1;
+                    println!("~ {id} {}: {}", video.channel, video.title);
+                    print_changes(before, video);
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, video) in &old.videos {
+            if !self.videos.contains_key(id) {
+                removed += 1;
+                println!("- {id} {}: {}{}", video.channel, video.title, at(video));
+            }
+        }
+
+        println!(
+            "Since {}: {added} videos added, {removed} removed, {changed} changed",
+            old.taken
+        );
+    }
+}
+
+fn at(video: &VideoSnapshot) -> String {
+    video
+        .placement
+        .as_ref()
+        .map(|placement| format!(" ({placement})"))
+        .unwrap_or_default()
+}
+
+fn print_changes(before: &VideoSnapshot, after: &VideoSnapshot) {
+    let field = |name: &str, before: &dyn std::fmt::Debug, after: &dyn std::fmt::Debug| {
+        println!("    {name}: {before:?} -> {after:?}");
+    };
+
+    if before.channel != after.channel {
+        field("channel", &before.channel, &after.channel);
+    }
+    if before.title != after.title {
+        field("title", &before.title, &after.title);
+    }
+    if before.date != after.date {
+        field("date", &before.date, &after.date);
+    }
+    if before.kind != after.kind {
+        field("kind", &before.kind.name(), &after.kind.name());
+    }
+    if before.placement != after.placement {
+        let show = |placement: &Option<Placement>| {
+            placement
+                .as_ref()
+                .map_or("filtered out".to_string(), Placement::to_string)
+        };
+        println!(
+            "    placement: {} -> {}",
+            show(&before.placement),
+            show(&after.placement)
+        );
+    }
+    for file in after.files.iter().filter(|f| !before.files.contains(f)) {
+        println!("    + {file:?}");
+    }
+    for file in before.files.iter().filter(|f| !after.files.contains(f)) {
+        println!("    - {file:?}");
+    }
+}
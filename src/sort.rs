@@ -0,0 +1,89 @@
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+
+use crate::CatalogueEntry;
+
+/// Order of channels, and of videos uploaded at the same time (yt-dlp often only knows the day).
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum SortOrder {
+    /// Channels by their exact name, videos of the same day by id
+    #[default]
+    Exact,
+    /// Ignoring case and accents and comparing numbers by value ("Episode 2" before
+    /// "Episode 10"), videos of the same day by title
+    Natural,
+}
+
+impl SortOrder {
+    pub fn channels(self, a: &str, b: &str) -> Ordering {
+        match self {
+            SortOrder::Exact => a.cmp(b),
+            SortOrder::Natural => natural_cmp(a, b),
+        }
+    }
+
+    /// Oldest first.
+    pub fn videos(self, a: &CatalogueEntry, b: &CatalogueEntry) -> Ordering {
+        a.date.cmp(&b.date).then_with(|| self.same_time(a, b))
+    }
+
+    /// Tie breaker of videos the dates don't tell apart.
+    pub fn same_time(self, a: &CatalogueEntry, b: &CatalogueEntry) -> Ordering {
+        let by_title = match self {
+            SortOrder::Exact => Ordering::Equal,
+            SortOrder::Natural => natural_cmp(a.get_title(), b.get_title()),
+        };
+
+        by_title.then_with(|| a.json.id.cmp(&b.json.id))
+    }
+}
+
+/// Compares runs of digits by their value and the rest transliterated to ASCII and lowercased,
+/// falling back to the exact strings so different ones never compare equal.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a_key, b_key) = (
+        deunicode::deunicode(a).to_lowercase(),
+        deunicode::deunicode(b).to_lowercase(),
+    );
+    let (mut a_rest, mut b_rest) = (a_key.as_str(), b_key.as_str());
+
+    loop {
+        let (a_chunk, a_next) = split_chunk(a_rest);
+        let (b_chunk, b_next) = split_chunk(b_rest);
+
+        let ordering = match (a_chunk, b_chunk) {
+            ("", "") => return a.cmp(b),
+            (a_chunk, b_chunk) if is_number(a_chunk) && is_number(b_chunk) => {
+                let (a_digits, b_digits) = (
+                    a_chunk.trim_start_matches('0'),
+                    b_chunk.trim_start_matches('0'),
+                );
+                a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits))
+            }
+            (a_chunk, b_chunk) => a_chunk.cmp(b_chunk),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        (a_rest, b_rest) = (a_next, b_next);
+    }
+}
+
+/// Splits off the leading run of digits or of anything else.
+fn split_chunk(s: &str) -> (&str, &str) {
+    let digits = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(s.len());
+
+    s.split_at(end)
+}
+
+fn is_number(chunk: &str) -> bool {
+    chunk.starts_with(|c: char| c.is_ascii_digit())
+}
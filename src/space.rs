@@ -0,0 +1,47 @@
+use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path};
+
+use anyhow::{Context, bail};
+
+use crate::stats::human_size;
+
+/// Fails when the filesystem of `target` has less than `needed` bytes available, so copying
+/// doesn't stop with a full disk halfway through. `force` skips the check.
+pub fn check(target: &Path, needed: u64, force: bool) -> anyhow::Result<()> {
+    if force || needed == 0 {
+        return Ok(());
+    }
+
+    let available = available(target)?;
+    if needed > available {
+        bail!(
+            "Copying needs {} but only {} are free on the filesystem of {target:?}; free up \
+             space, use --link-mode symlink or pass --force to start anyway",
+            human_size(needed),
+            human_size(available)
+        );
+    }
+
+    Ok(())
+}
+
+/// Bytes an unprivileged user can still write, on the filesystem of the nearest existing
+/// ancestor when `path` isn't created yet.
+fn available(path: &Path) -> anyhow::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."));
+    let c_path = CString::new(existing.as_os_str().as_bytes())?;
+
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is NUL terminated and `stat` is only read after a successful call.
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Can't get the free space of {existing:?}"));
+        }
+        stat.assume_init()
+    };
+
+    Ok(stat.f_bavail * stat.f_frsize)
+}
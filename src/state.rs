@@ -0,0 +1,191 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Episode, Season, SeasonedStructure, error::OrganizerError};
+
+pub const STATE_FILE_NAME: &str = ".ytdlp-organise.state.json";
+
+/// Organizer state kept in the target root between runs.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct State {
+    pub channels: BTreeMap<String, ChannelState>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ChannelState {
+    /// Season number assigned to every season key (year or playlist)
+    pub seasons: BTreeMap<String, usize>,
+    /// Season and episode number assigned to every video id
+    pub episodes: BTreeMap<String, EpisodeNumber>,
+    /// `channel_hash` of the last run that organized the channel, with `--skip-unchanged`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EpisodeNumber {
+    pub season: usize,
+    pub episode: usize,
+}
+
+impl State {
+    pub fn path(target: &Path) -> PathBuf {
+        target.join(STATE_FILE_NAME)
+    }
+
+    pub fn load(target: &Path) -> anyhow::Result<Self> {
+        let path = State::path(target);
+        let invalid = |reason: String| OrganizerError::StateError {
+            path: path.clone(),
+            reason,
+        };
+
+        match File::open(&path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))
+                .map_err(|err| invalid(err.to_string()))?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(State::default()),
+            Err(err) => Err(invalid(err.to_string()))?,
+        }
+    }
+
+    /// Writes the state, keeping daily backups of the previous one (see `backup::rotate_daily`).
+    pub fn save(&self, target: &Path) -> anyhow::Result<()> {
+        let path = State::path(target);
+        crate::backup::rotate_daily(&path)?;
+        crate::write_json(&path, self)
+    }
+
+    /// Whether the channel was organized with the same `hash` before.
+    pub fn is_unchanged(&self, channel: &str, hash: &str) -> bool {
+        self.channels
+            .get(channel)
+            .and_then(|state| state.hash.as_deref())
+            == Some(hash)
+    }
+
+    pub fn set_hash(&mut self, channel: &str, hash: String) {
+        self.channels.entry(channel.to_string()).or_default().hash = Some(hash);
+    }
+
+    /// Replaces the computed season and episode numbers of a channel with the ones assigned in
+    /// previous runs. New seasons are appended after the known ones, new episodes keep their
+    /// computed number when it is still free and are appended to their season otherwise, so
+    /// numbers once handed out never change.
+    pub fn apply_numbering(&mut self, channel: &mut SeasonedStructure) {
+        let state = self
+            .channels
+            .entry(channel.channel_name.clone())
+            .or_default();
+
+        let mut next_season = state.seasons.values().max().copied().unwrap_or(0) + 1;
+        let mut taken = state
+            .episodes
+            .values()
+            .copied()
+            .collect::<std::collections::HashSet<_>>();
+        let mut last_episode = HashMap::<usize, usize>::new();
+        for n in state.episodes.values() {
+            let last = last_episode.entry(n.season).or_default();
+            *last = (*last).max(n.episode);
+        }
+
+        let mut numbered = Vec::new();
+        for season in std::mem::take(&mut channel.seasons) {
+            let season_number = if season.number == 0 {
+                0
+            } else {
+                // The number the gap policy asks for, unless a season already got it.
+                *state.seasons.entry(season.key.clone()).or_insert_with(|| {
+                    let number = season.number.max(next_season);
+                    next_season = number + 1;
+                    number
+                })
+            };
+
+            for ep in season.videos {
+                let number = match state.episodes.get(&ep.entry.json.id) {
+                    Some(number) => *number,
+                    None => {
+                        let last = last_episode.entry(season_number).or_default();
+                        let mut number = EpisodeNumber {
+                            season: season_number,
+                            episode: ep.number,
+                        };
+                        if taken.contains(&number) {
+                            number.episode = *last + 1;
+                        }
+                        *last = (*last).max(number.episode);
+
+                        taken.insert(number);
+                        state.episodes.insert(ep.entry.json.id.clone(), number);
+                        number
+                    }
+                };
+
+                numbered.push((number, season.key.clone(), ep.entry));
+            }
+        }
+
+        numbered.sort_by_key(|(number, _, _)| (number.season, number.episode));
+        channel.seasons = numbered
+            .into_iter()
+            .chunk_by(|(number, _, _)| number.season)
+            .into_iter()
+            .map(|(season, episodes)| {
+                let episodes = episodes.collect::<Vec<_>>();
+                Season {
+                    number: season,
+                    key: episodes[0].1.clone(),
+                    videos: episodes
+                        .into_iter()
+                        .map(|(number, _, entry)| Episode {
+                            number: number.episode,
+                            entry,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+    }
+}
+
+/// Hash of everything organizing a channel depends on: the numbers, ids, dates, titles and files
+/// of its episodes, plus `salt` (the command line and the config file, so changed options aren't
+/// skipped).
+pub fn channel_hash(channel: &SeasonedStructure, salt: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(channel.channel_name.as_bytes());
+    for season in &channel.seasons {
+        for ep in &season.videos {
+            let entry = ep.entry;
+            hasher.update(
+                format!(
+                    "\0{}\0{}\0{}\0{}\0{}",
+                    season.number,
+                    ep.number,
+                    entry.json.id,
+                    entry.date,
+                    entry.get_title()
+                )
+                .as_bytes(),
+            );
+            for file in &entry.path {
+                hasher.update(b"\0");
+                hasher.update(file.as_os_str().as_encoded_bytes());
+            }
+        }
+    }
+
+    format!("{:x}", hasher.finalize())
+}
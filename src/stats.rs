@@ -0,0 +1,123 @@
+use anyhow::bail;
+use serde::Serialize;
+
+use crate::{SeasonedStructure, list::OutputFormat};
+
+#[derive(Serialize)]
+pub struct ChannelStats {
+    pub channel: String,
+    pub videos: usize,
+    pub size: u64,
+    pub seasons: Vec<SeasonStats>,
+}
+
+#[derive(Serialize)]
+pub struct SeasonStats {
+    pub season: usize,
+    pub videos: usize,
+    pub size: u64,
+}
+
+/// Sums the size of media files per channel and season, biggest channels first.
+pub fn collect(structure: &[SeasonedStructure]) -> Vec<ChannelStats> {
+    let mut stats = structure
+        .iter()
+        .map(|channel| {
+            let seasons = channel
+                .seasons
+                .iter()
+                .map(|season| SeasonStats {
+                    season: season.number,
+                    videos: season.videos.len(),
+                    size: season
+                        .videos
+                        .iter()
+                        .flat_map(|ep| ep.entry.get_media())
+                        .filter_map(|path| std::fs::metadata(path).ok())
+                        .map(|meta| meta.len())
+                        .sum(),
+                })
+                .collect::<Vec<_>>();
+
+            ChannelStats {
+                channel: channel.channel_name.clone(),
+                videos: seasons.iter().map(|s| s.videos).sum(),
+                size: seasons.iter().map(|s| s.size).sum(),
+                seasons,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    stats.sort_by_key(|c| std::cmp::Reverse(c.size));
+    stats
+}
+
+pub fn print(stats: &[ChannelStats], format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(stats)?),
+        OutputFormat::Text | OutputFormat::Table => print_table(stats),
+        OutputFormat::Tree => bail!("stats can't be drawn as a tree, use --format table or json"),
+    }
+
+    Ok(())
+}
+
+fn print_table(stats: &[ChannelStats]) {
+    let width = stats
+        .iter()
+        .map(|c| c.channel.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Channel".len());
+
+    println!(
+        "{:<width$}  {:>6}  {:>6}  {:>10}",
+        "Channel", "Season", "Videos", "Size"
+    );
+    for channel in stats {
+        println!(
+            "{:<width$}  {:>6}  {:>6}  {:>10}",
+            channel.channel,
+            "",
+            channel.videos,
+            human_size(channel.size)
+        );
+
+        for season in &channel.seasons {
+            println!(
+                "{:<width$}  {:>6}  {:>6}  {:>10}",
+                "",
+                season.season,
+                season.videos,
+                human_size(season.size)
+            );
+        }
+    }
+
+    let total: u64 = stats.iter().map(|c| c.size).sum();
+    let videos: usize = stats.iter().map(|c| c.videos).sum();
+    println!(
+        "{:<width$}  {:>6}  {:>6}  {:>10}",
+        "Total",
+        "",
+        videos,
+        human_size(total)
+    );
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
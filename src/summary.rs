@@ -0,0 +1,143 @@
+use std::{ops::AddAssign, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::retention::PrunedEpisode;
+
+/// What a run changed in the target.
+#[derive(Default, Clone, Serialize)]
+pub struct Summary {
+    pub directories: usize,
+    pub links: usize,
+    pub artwork: usize,
+    /// NFO files written or updated
+    pub nfo: usize,
+    /// Titles of episodes that got at least one new file
+    pub new_episodes: Vec<String>,
+}
+
+impl Summary {
+    pub fn has_changes(&self) -> bool {
+        self.directories + self.links + self.artwork + self.nfo > 0
+    }
+}
+
+impl AddAssign<&Summary> for Summary {
+    fn add_assign(&mut self, other: &Summary) {
+        self.directories += other.directories;
+        self.links += other.links;
+        self.artwork += other.artwork;
+        self.nfo += other.nfo;
+        self.new_episodes.extend(other.new_episodes.iter().cloned());
+    }
+}
+
+#[derive(Serialize)]
+pub struct ChannelReport {
+    pub channel: String,
+    #[serde(flatten)]
+    pub summary: Summary,
+}
+
+/// A video whose info.json was found without a media file.
+#[derive(Serialize)]
+pub struct MissingMedia {
+    pub channel: String,
+    pub title: String,
+    pub id: String,
+    pub info_json: PathBuf,
+}
+
+/// A video left out for a media file too small to be a complete download.
+#[derive(Serialize)]
+pub struct BrokenMedia {
+    pub channel: String,
+    pub title: String,
+    pub id: String,
+    pub media: PathBuf,
+    /// In bytes
+    pub size: u64,
+}
+
+/// A file or directory of the source the scan couldn't read, with everything below it.
+#[derive(Clone, Serialize)]
+pub struct InaccessiblePath {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A video downloaded before its premiere, held back until yt-dlp fetches it again.
+#[derive(Serialize)]
+pub struct PendingPremiere {
+    pub channel: String,
+    pub title: String,
+    pub id: String,
+    /// When it was scheduled to premiere, as far as the info.json knows
+    pub scheduled: String,
+}
+
+/// What a run changed in one target root.
+#[derive(Serialize)]
+pub struct TargetReport {
+    pub target: PathBuf,
+    #[serde(flatten)]
+    pub summary: Summary,
+}
+
+/// Outcome of a whole run, as sent to notification targets.
+#[derive(Default, Serialize)]
+pub struct Report {
+    pub dry_run: bool,
+    /// Videos found in the source
+    pub catalogue: usize,
+    pub new_episodes: usize,
+    /// Videos of the catalogue not placed in any target
+    pub pending: usize,
+    /// Channels that were changed by the run
+    pub channels: Vec<ChannelReport>,
+    /// Every target root organized, including mirrors and route targets
+    pub targets: Vec<TargetReport>,
+    /// Episodes removed to keep the library under `--max-library-size`
+    pub pruned: Vec<PrunedEpisode>,
+    /// Videos left out by `--require-media`, to download again
+    pub missing_media: Vec<MissingMedia>,
+    /// Videos left out for media files under `--min-media-size`
+    pub broken_media: Vec<BrokenMedia>,
+    /// Parts of the source left out of the scan as they couldn't be read
+    pub inaccessible: Vec<InaccessiblePath>,
+    /// Videos left out as their premiere hadn't started when they were downloaded
+    pub pending_premieres: Vec<PendingPremiere>,
+    /// Playlist collections written or updated
+    pub collections: usize,
+    /// M3U playlists written or updated
+    pub playlists: usize,
+    /// Whether the `--feed` was written or updated
+    pub feed: bool,
+    pub errors: Vec<String>,
+    /// Stopped early by SIGINT or SIGTERM
+    pub interrupted: bool,
+    #[serde(skip)]
+    pub total: Summary,
+}
+
+impl Report {
+    pub fn has_changes(&self) -> bool {
+        self.total.has_changes()
+            || !self.pruned.is_empty()
+            || self.collections > 0
+            || self.playlists > 0
+            || self.feed
+    }
+
+    pub fn add_channel(&mut self, channel: &str, summary: Summary) {
+        self.total += &summary;
+        self.new_episodes += summary.new_episodes.len();
+
+        if summary.has_changes() {
+            self.channels.push(ChannelReport {
+                channel: channel.to_string(),
+                summary,
+            });
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate};
+use serde_json::Value;
+
+/// A yt-dlp output template (`-o`), optionally prefixed with an output type such as
+/// `thumbnail:` or `infojson:`.
+#[derive(Clone)]
+pub struct OutputTemplate {
+    template: String,
+}
+
+impl std::str::FromStr for OutputTemplate {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template = match s.split_once(':') {
+            Some((kind, rest))
+                if !kind.is_empty() && kind.chars().all(|c| c.is_ascii_lowercase() || c == '_') =>
+            {
+                rest
+            }
+            _ => s,
+        };
+
+        Ok(Self {
+            template: template.to_string(),
+        })
+    }
+}
+
+impl OutputTemplate {
+    /// Directory (relative to the download root) and file stem the template produces for the
+    /// given video.
+    pub fn locate(&self, root: &Path, info: &Value) -> (PathBuf, String) {
+        let template = self
+            .template
+            .strip_suffix(".%(ext)s")
+            .unwrap_or(&self.template);
+        let rendered = root.join(render(template, info));
+
+        let stem = rendered
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let dir = rendered.parent().unwrap_or(root).to_path_buf();
+
+        (dir, stem)
+    }
+}
+
+/// Renders `%(field)s` style placeholders the same way yt-dlp does for the subset of the syntax
+/// that matters for locating files: alternatives (`a,b`), defaults (`|x`), date formatting
+/// (`>%Y`) and `s`/`d` conversions with padding. Missing fields become `NA`.
+pub fn render(template: &str, info: &Value) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("%(") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let Some(end) = after.find(')') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+
+        let spec = &after[..end];
+        let conversion = &after[end + 1..];
+        let conv_len = conversion
+            .find(|c: char| c.is_ascii_alphabetic())
+            .map_or(0, |i| i + 1);
+        let (conversion, tail) = conversion.split_at(conv_len);
+
+        out.push_str(&render_field(spec, conversion, info));
+        rest = tail;
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn render_field(spec: &str, conversion: &str, info: &Value) -> String {
+    let (spec, default) = match spec.split_once('|') {
+        Some((spec, default)) => (spec, Some(default)),
+        None => (spec, None),
+    };
+    let (fields, date_format) = match spec.split_once('>') {
+        Some((fields, format)) => (fields, Some(format)),
+        None => (spec, None),
+    };
+
+    let value = fields
+        .split(',')
+        .filter_map(|field| lookup(info, field.trim()))
+        .find(|v| !v.is_null());
+
+    let Some(value) = value else {
+        return default.unwrap_or("NA").to_string();
+    };
+
+    let text = match (date_format, value) {
+        (Some(format), Value::String(s)) => NaiveDate::parse_from_str(s, "%Y%m%d")
+            .map(|d| d.format(format).to_string())
+            .unwrap_or_else(|_| s.clone()),
+        (Some(format), Value::Number(n)) => n
+            .as_i64()
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .map(|d| d.format(format).to_string())
+            .unwrap_or_else(|| n.to_string()),
+        (_, Value::String(s)) => s.clone(),
+        (_, other) => other.to_string(),
+    };
+
+    sanitize(&apply_conversion(&text, conversion))
+}
+
+fn lookup<'a>(info: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(info, |value, key| match key.parse::<usize>() {
+            Ok(index) => value.get(index),
+            Err(_) => value.get(key),
+        })
+}
+
+fn apply_conversion(text: &str, conversion: &str) -> String {
+    let Some(kind) = conversion.chars().last() else {
+        return text.to_string();
+    };
+    let flags = &conversion[..conversion.len() - 1];
+    let width = flags
+        .trim_start_matches(['0', '-', '+', ' ', '#'])
+        .split('.')
+        .next()
+        .and_then(|w| w.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    match kind {
+        'd' | 'i' if flags.starts_with('0') => format!("{text:0>width$}"),
+        _ if flags.starts_with('-') => format!("{text:<width$}"),
+        _ => format!("{text:>width$}"),
+    }
+}
+
+/// yt-dlp replaces path separators in field values so they can't create directories.
+fn sanitize(value: &str) -> String {
+    value.replace('/', "\u{29f8}")
+}
@@ -0,0 +1,137 @@
+use std::{
+    fmt::{self, Display},
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use clap::ValueEnum;
+
+use crate::logfile;
+
+#[derive(ValueEnum, Clone, Copy, Default, PartialEq)]
+pub enum ColorChoice {
+    /// Colors when writing to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether stdout and stderr get colors, set once from `--color`.
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Whether progress is printed, off while an event stream goes to stdout.
+static CONSOLE: AtomicBool = AtomicBool::new(true);
+
+pub fn set_console(enabled: bool) {
+    CONSOLE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn init(choice: ColorChoice) {
+    let enabled = |terminal: bool| match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            terminal
+                && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+        }
+    };
+    STDOUT.store(enabled(std::io::stdout().is_terminal()), Ordering::Relaxed);
+    STDERR.store(enabled(std::io::stderr().is_terminal()), Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy)]
+pub enum Color {
+    /// Created and placed
+    Green,
+    /// Skipped, kept and removed
+    Yellow,
+    /// Errors
+    Red,
+    /// Details after the path
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Color::Green => "32",
+            Color::Yellow => "33",
+            Color::Red => "31",
+            Color::Dim => "2",
+        }
+    }
+}
+
+/// Text wrapped in the escape codes of a color when its stream gets colors. Width and alignment
+/// apply to the text inside the codes, so padded columns still line up.
+pub struct Painted<T> {
+    text: T,
+    color: Color,
+    enabled: bool,
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.enabled {
+            return self.text.fmt(f);
+        }
+
+        write!(f, "\x1b[{}m", self.color.code())?;
+        self.text.fmt(f)?;
+        write!(f, "\x1b[0m")
+    }
+}
+
+/// `text` in `color` for stdout.
+pub fn paint<T: Display>(color: Color, text: T) -> Painted<T> {
+    Painted {
+        text,
+        color,
+        enabled: STDOUT.load(Ordering::Relaxed),
+    }
+}
+
+/// `text` in `color` for stderr.
+pub fn paint_err<T: Display>(color: Color, text: T) -> Painted<T> {
+    Painted {
+        text,
+        color,
+        enabled: STDERR.load(Ordering::Relaxed),
+    }
+}
+
+/// `text` cut to `width` characters, ending in `…` when shortened.
+pub fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut fitted = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    fitted.push('…');
+    fitted
+}
+
+/// Prints a line of progress: `action` right-aligned in its color, then what it applies to.
+pub fn say(color: Color, action: &str, what: impl Display) {
+    print(format_args!("{:>12} {what}", paint(color, action)));
+}
+
+/// Prints a line of progress unless stdout carries events, and adds it to the `--log-file`.
+pub fn print(line: impl Display) {
+    logfile::line(&line);
+    if CONSOLE.load(Ordering::Relaxed) {
+        println!("{line}");
+    }
+}
+
+/// Prints an error or a warning to stderr, and adds it to the `--log-file`.
+pub fn error(color: Color, message: impl Display) {
+    logfile::line(&message);
+    eprintln!("{}", paint_err(color, message));
+}
@@ -0,0 +1,87 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Read, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::signals;
+
+/// Bytes per second copies may write, 0 for no limit. Set once from `--bwlimit`.
+static LIMIT: AtomicU64 = AtomicU64::new(0);
+
+const CHUNK: usize = 1 << 20;
+
+pub fn set_limit(bytes_per_second: u64) {
+    LIMIT.store(bytes_per_second, Ordering::Relaxed);
+}
+
+pub fn limit() -> Option<u64> {
+    Some(LIMIT.load(Ordering::Relaxed)).filter(|limit| *limit > 0)
+}
+
+/// `std::fs::copy`, paced to the `--bwlimit` when one is set. A throttled copy stops early when
+/// the run is interrupted, removing what it wrote.
+pub fn copy(source: &Path, target: &Path) -> io::Result<u64> {
+    let Some(limit) = limit() else {
+        return std::fs::copy(source, target);
+    };
+
+    let result = copy_paced(source, target, limit);
+    if result.is_err() {
+        let _ = std::fs::remove_file(target);
+    }
+
+    result
+}
+
+fn copy_paced(source: &Path, target: &Path, limit: u64) -> io::Result<u64> {
+    let mut input = File::open(source)?;
+    // Truncates what an interrupted run left behind, like `std::fs::copy` does.
+    let mut output = File::create(target)?;
+    output.set_permissions(input.metadata()?.permissions())?;
+
+    let start = Instant::now();
+    let mut buffer = vec![0; CHUNK];
+    let mut written = 0;
+    loop {
+        let read = match input.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        output.write_all(&buffer[..read])?;
+        written += read as u64;
+
+        // Sleeps until the average rate since the start is back under the limit.
+        let due = Duration::from_secs_f64(written as f64 / limit as f64);
+        if !signals::sleep(due.saturating_sub(start.elapsed())) {
+            return Err(io::Error::other("interrupted while copying"));
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_paced_replaces_a_stale_part_file() {
+        let dir = std::env::temp_dir().join(format!("throttle-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("video.mp4");
+        let part = dir.join(".video.mp4.part");
+        std::fs::write(&source, b"complete video").unwrap();
+        std::fs::write(&part, b"longer leftover of a crashed run").unwrap();
+
+        let written = copy_paced(&source, &part, u64::MAX).unwrap();
+
+        assert_eq!(written, 14);
+        assert_eq!(std::fs::read(&part).unwrap(), b"complete video");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::Context;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+
+use crate::term::{self, Color};
+
+/// Directory of the target (or source) root that `--trash local` moves deleted files into.
+pub const TRASH_DIR: &str = ".trash";
+
+/// Markers keeping the trash out of the source scan and the media server's library.
+const MARKERS: &[&str] = &[".nomedia", ".ignore"];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const XDG_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum TrashPlace {
+    /// A `.trash` directory in the target (or source), one subdirectory per day
+    Local,
+    /// The desktop trash of the user, `$XDG_DATA_HOME/Trash`
+    Xdg,
+}
+
+/// Where deleted files go, set once from `--trash`.
+static TRASH: OnceLock<TrashPlace> = OnceLock::new();
+
+pub fn set(place: TrashPlace) {
+    let _ = TRASH.set(place);
+}
+
+/// Deletes `path`, or moves it to the trash when one is set. Local trash keeps its place below
+/// `root`, or below its own directory when `root` doesn't contain it.
+pub fn remove(path: &Path, root: &Path) -> io::Result<()> {
+    let Some(place) = TRASH.get() else {
+        return std::fs::remove_file(path);
+    };
+    // Fails like `remove_file` for a file that's already gone.
+    path.symlink_metadata()?;
+
+    match place {
+        TrashPlace::Local => {
+            let (root, relative) = match path.strip_prefix(root) {
+                Ok(relative) => (root, relative),
+                Err(_) => (
+                    path.parent().unwrap_or(Path::new(".")),
+                    Path::new(path.file_name().unwrap_or_default()),
+                ),
+            };
+            let trash = root.join(TRASH_DIR);
+            std::fs::create_dir_all(&trash)?;
+            for marker in MARKERS {
+                let marker = trash.join(marker);
+                if !marker.exists() {
+                    File::create(marker)?;
+                }
+            }
+
+            let target = free_path(
+                &trash
+                    .join(Local::now().format(DATE_FORMAT).to_string())
+                    .join(relative),
+            );
+            if let Some(dir) = target.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            move_file(path, &target)
+        }
+        TrashPlace::Xdg => {
+            let trash = xdg_trash()?;
+            let (files, info) = (trash.join("files"), trash.join("info"));
+            std::fs::create_dir_all(&files)?;
+            std::fs::create_dir_all(&info)?;
+
+            // The info file is created first, claiming the name.
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let mut n = 0;
+            let (name, mut info_file) = loop {
+                let candidate = match n {
+                    0 => name.to_string(),
+                    n => format!("{name}.{n}"),
+                };
+                match File::create_new(info.join(format!("{candidate}.trashinfo"))) {
+                    Ok(file) if !files.join(&candidate).exists() => break (candidate, file),
+                    Ok(_) => {
+                        let _ = std::fs::remove_file(info.join(format!("{candidate}.trashinfo")));
+                    }
+                    Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                    Err(err) => return Err(err),
+                }
+                n += 1;
+            };
+            write!(
+                info_file,
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                encode(&std::path::absolute(path)?),
+                Local::now().format(XDG_DATE_FORMAT)
+            )?;
+
+            let result = move_file(path, &files.join(&name));
+            if result.is_err() {
+                let _ = std::fs::remove_file(info.join(format!("{name}.trashinfo")));
+            }
+            result
+        }
+    }
+}
+
+/// Deletes what the trash holds from below `root` for good: everything, or only what's been there
+/// longer than `days`. Returns the number of files deleted.
+pub fn empty(
+    root: &Path,
+    days: Option<u64>,
+    dry_run: bool,
+    verbose: bool,
+) -> anyhow::Result<usize> {
+    let today = Local::now().date_naive();
+    let expired = |date: NaiveDate| days.is_none_or(|days| (today - date).num_days() > days as i64);
+
+    let mut deleted = 0;
+    let trash = root.join(TRASH_DIR);
+    if let Ok(dirs) = std::fs::read_dir(&trash) {
+        for dir in dirs {
+            let dir = dir?.path();
+            let date = dir.file_name().and_then(|name| {
+                NaiveDate::parse_from_str(&name.to_string_lossy(), DATE_FORMAT).ok()
+            });
+            if !date.is_some_and(expired) {
+                continue;
+            }
+
+            if verbose {
+                term::say(Color::Yellow, "Emptying", dir.display());
+            }
+            deleted += walkdir::WalkDir::new(&dir)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| !e.file_type().is_dir())
+                .count();
+            if !dry_run {
+                std::fs::remove_dir_all(&dir)
+                    .with_context(|| format!("Can't empty the trash {dir:?}"))?;
+            }
+        }
+    }
+
+    // Only what was trashed from below `root` is touched in the desktop trash.
+    let Ok(trash) = xdg_trash() else {
+        return Ok(deleted);
+    };
+    let root = std::path::absolute(root)?;
+    let Ok(infos) = std::fs::read_dir(trash.join("info")) else {
+        return Ok(deleted);
+    };
+    for info in infos {
+        let info = info?.path();
+        let Some(name) = info
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_suffix(".trashinfo"))
+        else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&info) else {
+            continue;
+        };
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        };
+        let Some(path) = field("Path").map(decode) else {
+            continue;
+        };
+        let date = field("DeletionDate")
+            .and_then(|date| NaiveDateTime::parse_from_str(date, XDG_DATE_FORMAT).ok());
+        if !path.starts_with(&root) || !date.is_some_and(|date| expired(date.date())) {
+            continue;
+        }
+
+        if verbose {
+            term::say(Color::Yellow, "Emptying", path.display());
+        }
+        deleted += 1;
+        if !dry_run {
+            let file = trash.join("files").join(name);
+            match std::fs::remove_file(&file) {
+                Err(err) if err.kind() != ErrorKind::NotFound => {
+                    Err(err).with_context(|| format!("Can't empty the trash {file:?}"))?
+                }
+                _ => {}
+            }
+            std::fs::remove_file(&info)?;
+        }
+    }
+
+    Ok(deleted)
+}
+
+fn xdg_trash() -> io::Result<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .ok_or_else(|| {
+            io::Error::other("Neither XDG_DATA_HOME nor HOME is set, there's no desktop trash")
+        })?;
+
+    Ok(data.join("Trash"))
+}
+
+/// `path`, or `path` with ` (2)`, ` (3)`, ... added to its stem when taken.
+fn free_path(path: &Path) -> PathBuf {
+    if path.symlink_metadata().is_err() {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap()
+}
+
+/// Renames, or copies and deletes when the trash is on another filesystem.
+fn move_file(source: &Path, target: &Path) -> io::Result<()> {
+    match std::fs::rename(source, target) {
+        Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+            if source.is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(source)?, target)?;
+            } else {
+                std::fs::copy(source, target)?;
+            }
+            std::fs::remove_file(source)
+        }
+        result => result,
+    }
+}
+
+/// Percent-encodes a path for a `.trashinfo` file.
+fn encode(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn decode(path: &str) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(b) => {
+                decoded.push(b);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    PathBuf::from(std::ffi::OsString::from_vec(decoded))
+}
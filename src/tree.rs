@@ -0,0 +1,137 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+use crate::{
+    plan::{LibraryPlan, Operation},
+    term::{self, Color},
+};
+
+/// A line of a tree and the lines drawn below it.
+pub struct Node {
+    pub label: String,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(label: impl Into<String>, children: Vec<Node>) -> Self {
+        Self {
+            label: label.into(),
+            children,
+        }
+    }
+}
+
+/// Prints every node with its children indented below it, connected by branches.
+pub fn print(nodes: &[Node]) {
+    for node in nodes {
+        println!("{}", node.label);
+        print_children(&node.children, "");
+    }
+}
+
+fn print_children(nodes: &[Node], indent: &str) {
+    for (i, node) in nodes.iter().enumerate() {
+        let (branch, below) = match i + 1 == nodes.len() {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+        println!("{indent}{branch}{}", node.label);
+        print_children(&node.children, &format!("{indent}{below}"));
+    }
+}
+
+/// A directory of the target with what a plan changes below it.
+#[derive(Default)]
+struct Dir {
+    created: bool,
+    dirs: BTreeMap<String, Dir>,
+    /// Names of the files, whether they are removed rather than placed or written
+    files: BTreeMap<String, bool>,
+}
+
+impl Dir {
+    fn at(&mut self, components: &[String]) -> &mut Dir {
+        components
+            .iter()
+            .fold(self, |dir, name| dir.dirs.entry(name.clone()).or_default())
+    }
+
+    /// Files placed and removed below the directory.
+    fn counts(&self) -> (usize, usize) {
+        let removed = self.files.values().filter(|removed| **removed).count();
+        self.dirs.values().map(Dir::counts).fold(
+            (self.files.len() - removed, removed),
+            |(placed, removed), (p, r)| (placed + p, removed + r),
+        )
+    }
+
+    fn into_nodes(self) -> Vec<Node> {
+        let dirs = self.dirs.into_iter().map(|(name, dir)| {
+            let marker = match dir.created {
+                true => format!("{} ", term::paint(Color::Green, "+")),
+                false => String::new(),
+            };
+            let label = format!("{marker}{name}/ {}", describe(dir.counts()));
+            Node::new(label, dir.into_nodes())
+        });
+        let files = self.files.into_iter().map(|(name, removed)| {
+            let marker = match removed {
+                true => term::paint(Color::Yellow, "-"),
+                false => term::paint(Color::Green, "+"),
+            };
+            Node::new(format!("{marker} {name}"), Vec::new())
+        });
+
+        dirs.chain(files).collect()
+    }
+}
+
+fn describe((placed, removed): (usize, usize)) -> String {
+    let counts = match (placed, removed) {
+        (placed, 0) => format!("{placed} files"),
+        (0, removed) => format!("{removed} removed"),
+        (placed, removed) => format!("{placed} files, {removed} removed"),
+    };
+    term::paint(Color::Dim, format!("({counts})")).to_string()
+}
+
+/// What the plan of a dry run does to its target: the directories and files it creates, places,
+/// writes and removes, below the target root. The root is labelled `shown_as`, the URL for an
+/// `ssh://` target planned in its staging directory.
+pub fn of_plan(library: &LibraryPlan, shown_as: &Path) -> Node {
+    let mut root = Dir::default();
+    for op in &library.operations {
+        let (path, removed) = match op {
+            Operation::CreateDir { path } => {
+                let components = components(path.strip_prefix(&library.target).unwrap_or(path));
+                root.at(&components).created = true;
+                continue;
+            }
+            Operation::Link { target, .. }
+            | Operation::HardLink { target, .. }
+            | Operation::Download { target, .. } => (target, false),
+            Operation::Write { path, .. } => (path, false),
+            Operation::Remove { path } => (path, true),
+        };
+
+        let mut components = components(path.strip_prefix(&library.target).unwrap_or(path));
+        let Some(name) = components.pop() else {
+            continue;
+        };
+        root.at(&components).files.insert(name, removed);
+    }
+
+    let label = format!("{} {}", shown_as.display(), describe(root.counts()));
+    Node::new(label, root.into_nodes())
+}
+
+fn components(path: &std::path::Path) -> Vec<String> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
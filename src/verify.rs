@@ -0,0 +1,95 @@
+use std::{collections::HashSet, path::Path};
+
+use anyhow::bail;
+
+use crate::{LIBRARY_IGNORE_MARKER, Library, MEDIA_EXTENSIONS, VideoCatalogue, manifest::Manifest};
+
+/// Compares the channels of the source with what the targets hold: channels none of whose videos
+/// were placed (dropped by a filter, a route or an error) and top level directories of a target
+/// without a single video of the source (a renamed channel, or leftovers of another tool).
+/// Fails when either turns up.
+pub fn run(catalogue: &VideoCatalogue, libraries: &[Library]) -> anyhow::Result<()> {
+    let targets = libraries
+        .iter()
+        .filter_map(|library| library.target.as_deref())
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        bail!("Nothing to verify, pass --target or set \"target\" in the config");
+    }
+
+    let mut placed = HashSet::new();
+    let mut orphans = Vec::new();
+    for target in &targets {
+        let manifest = Manifest::load(target)?;
+        let mut used = HashSet::new();
+        for (link, known) in &manifest.links {
+            if target.join(link).symlink_metadata().is_err() {
+                continue;
+            }
+            placed.insert(known.id.as_str().to_owned());
+            if let Some(top) = link.components().next() {
+                used.insert(top.as_os_str().to_owned());
+            }
+        }
+
+        for dir in std::fs::read_dir(target)? {
+            let dir = dir?;
+            let name = dir.file_name();
+            // Hidden directories and ones the media server ignores (`--collections`) aren't shows.
+            if !dir.file_type()?.is_dir()
+                || name.to_string_lossy().starts_with('.')
+                || dir.path().join(LIBRARY_IGNORE_MARKER).exists()
+            {
+                continue;
+            }
+            if !used.contains(&name) {
+                orphans.push((dir.path(), count_media(&dir.path())));
+            }
+        }
+    }
+
+    let missing = catalogue
+        .by_channel()
+        .into_iter()
+        .filter(|(_, entries)| !entries.iter().any(|entry| placed.contains(&entry.json.id)))
+        .map(|(channel, entries)| (channel, entries.len()))
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        println!("Channels of the source with nothing in the target:");
+        for (channel, videos) in &missing {
+            println!("  {channel} ({videos} videos)");
+        }
+    }
+    if !orphans.is_empty() {
+        println!("Directories of the target without any video of the source:");
+        for (dir, media) in &orphans {
+            println!("  {dir:?} ({media} media files)");
+        }
+    }
+
+    if !missing.is_empty() || !orphans.is_empty() {
+        bail!(
+            "{} channels missing from the target, {} directories not from the source",
+            missing.len(),
+            orphans.len()
+        );
+    }
+    println!("Every channel of the source is in the target");
+
+    Ok(())
+}
+
+fn count_media(dir: &Path) -> usize {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        })
+        .count()
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::OrganizerError,
+    media_server::{MediaServer, UserData},
+    term::{self, Color},
+    write_json,
+};
+
+pub const WATCHED_FILE_NAME: &str = ".ytdlp-organise.watched.json";
+
+/// Transfers the media server hasn't picked up within this many days are given up.
+const GIVE_UP_DAYS: i64 = 30;
+
+/// Watch state of moved episodes, kept in the target root until the media server has scanned
+/// their new paths.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PendingTransfers {
+    pub transfers: Vec<Transfer>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Transfer {
+    /// The new path of the episode, as the server sees it
+    pub path: PathBuf,
+    pub moved: String,
+    pub users: Vec<UserTransfer>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UserTransfer {
+    pub user: String,
+    #[serde(flatten)]
+    pub data: UserData,
+}
+
+fn is_watched(data: &UserData) -> bool {
+    data.played || data.play_count > 0 || data.playback_position_ticks > 0
+}
+
+impl PendingTransfers {
+    pub fn path(target: &Path) -> PathBuf {
+        target.join(WATCHED_FILE_NAME)
+    }
+
+    pub fn load(target: &Path) -> anyhow::Result<Self> {
+        let path = PendingTransfers::path(target);
+        match File::open(&path) {
+            Ok(file) => Ok(
+                serde_json::from_reader(BufReader::new(file)).map_err(|err| {
+                    OrganizerError::StateError {
+                        path: path.clone(),
+                        reason: err.to_string(),
+                    }
+                })?,
+            ),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(PendingTransfers::default()),
+            Err(err) => Err(OrganizerError::io(&path)(err))?,
+        }
+    }
+
+    /// Writes the pending transfers, removing the file once none are left.
+    pub fn save(&self, target: &Path) -> anyhow::Result<()> {
+        let path = PendingTransfers::path(target);
+        if self.transfers.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            };
+        }
+
+        write_json(&path, self)
+    }
+}
+
+/// Reads the watch state of every user for the episodes about to be moved from the first to the
+/// second path, and adds it to the transfers pending in `target`.
+pub fn capture(
+    server: &MediaServer,
+    moves: &[(PathBuf, PathBuf)],
+    target: &Path,
+    verbose: bool,
+) -> anyhow::Result<()> {
+    if moves.is_empty() {
+        return Ok(());
+    }
+
+    let mut transfers = HashMap::<PathBuf, Vec<UserTransfer>>::new();
+    for user in server.users()? {
+        let items = server.items(&user)?;
+        for (from, to) in moves {
+            let Some(item) = items.get(&server.server_path(from)) else {
+                continue;
+            };
+            if is_watched(&item.user_data) {
+                transfers
+                    .entry(server.server_path(to))
+                    .or_default()
+                    .push(UserTransfer {
+                        user: user.clone(),
+                        data: item.user_data.clone(),
+                    });
+            }
+        }
+    }
+
+    if verbose && !transfers.is_empty() {
+        term::print(format_args!(
+            "Keeping the watch state of {} moved episodes until the media server scans them",
+            transfers.len()
+        ));
+    }
+    let mut pending = PendingTransfers::load(target)?;
+    let moved = Local::now().to_rfc3339();
+    for (path, users) in transfers {
+        pending.transfers.retain(|transfer| transfer.path != path);
+        pending.transfers.push(Transfer {
+            path,
+            moved: moved.clone(),
+            users,
+        });
+    }
+    pending.save(target)
+}
+
+/// Sets the watch state kept by `capture` on the episodes the server knows under their new path
+/// by now. The rest waits for a later run, for at most `GIVE_UP_DAYS`.
+pub fn apply(server: &MediaServer, target: &Path, verbose: bool) -> anyhow::Result<()> {
+    let mut pending = PendingTransfers::load(target)?;
+    if pending.transfers.is_empty() {
+        return Ok(());
+    }
+
+    let mut items = HashMap::new();
+    let mut done = Vec::new();
+    for (index, transfer) in pending.transfers.iter().enumerate() {
+        let mut found = true;
+        for user in &transfer.users {
+            if !items.contains_key(&user.user) {
+                items.insert(user.user.clone(), server.items(&user.user)?);
+            }
+            let Some(item) = items[&user.user].get(&transfer.path) else {
+                found = false;
+                break;
+            };
+
+            let data = &user.data;
+            if data.played {
+                server.post(
+                    &format!("/Users/{}/PlayedItems/{}", user.user, item.id),
+                    json!({}),
+                )?;
+            }
+            server.post(
+                &format!("/Users/{}/Items/{}/UserData", user.user, item.id),
+                json!({
+                    "Played": data.played,
+                    "PlayCount": data.play_count,
+                    "PlaybackPositionTicks": data.playback_position_ticks,
+                }),
+            )?;
+        }
+
+        if found {
+            if verbose {
+                term::print(format_args!(
+                    "Restored the watch state of {:?}",
+                    transfer.path
+                ));
+            }
+            done.push(index);
+        }
+    }
+
+    let now = Local::now();
+    let mut index = 0;
+    pending.transfers.retain(|transfer| {
+        let keep = !done.contains(&index);
+        index += 1;
+        let stale = DateTime::parse_from_rfc3339(&transfer.moved)
+            .is_ok_and(|moved| (now - moved.with_timezone(&Local)).num_days() > GIVE_UP_DAYS);
+        if keep && stale {
+            let message = format!(
+                "Giving up on the watch state of {:?}, the media server never listed it",
+                transfer.path
+            );
+            term::error(Color::Yellow, message);
+        }
+        keep && !stale
+    });
+    pending.save(target)
+}